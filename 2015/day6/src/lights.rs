@@ -3,19 +3,37 @@ pub mod lights {
     use regex::Regex;
     use std::fmt;
 
-    const CG_INSTRUCTION: usize = 1;
-    const CG_LOCATIONS: usize = 2;
-    const CG_TOTAL: usize = 6;
+    const CG_LOCATIONS: usize = 1;
+    const CG_TOTAL: usize = 5;
+
+    /// The puzzle's light grid is 1000 bulbs on a side, addressed 0
+    /// through 999.
+    pub const GRID_SIZE: usize = 1000;
+
+    /// The verbs an instruction may begin with.  `set` and `dim` take
+    /// a brightness argument before the range, e.g. `set 7 0,0 through
+    /// 9,9`.
+    const VERBS: [&str; 6] = ["toggle", "turn on", "turn off", "set", "dim", "invert"];
 
     #[derive(Debug, PartialEq)]
     pub struct Location(usize, usize);
 
+    impl fmt::Display for Location {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{},{}", self.0, self.1)
+        }
+    }
+
     pub enum BulbAction {
         TurnOn,
         TurnOff,
         Toggle,
+        Set(u32),
+        Dim(u32),
+        Invert,
     }
 
+    #[allow(dead_code)]
     #[derive(PartialEq, Copy, Clone)]
     pub enum BulbValue {
         On,
@@ -44,8 +62,8 @@ pub mod lights {
     impl fmt::Display for BulbValue {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             match self {
-                BulbValue::On => write!(f, "{}", "*"),
-                BulbValue::Off => write!(f, "{}", "-"),
+                BulbValue::On => write!(f, "*"),
+                BulbValue::Off => write!(f, "-"),
             }
         }
     }
@@ -61,24 +79,44 @@ pub mod lights {
         }
     }
 
+    /// A grid of `width` strands, each `height` bulbs long.  A bulb at
+    /// Location(x, y) lives at `grid[x][y]`.
     pub struct LightGrid {
-        pub grid: [[u32; 1000]; 1000],
+        pub width: usize,
+        pub height: usize,
+        pub grid: Vec<Vec<u32>>,
     }
 
     impl LightGrid {
+        pub fn new(width: usize, height: usize) -> Self {
+            LightGrid {
+                width,
+                height,
+                grid: vec![vec![0; height]; width],
+            }
+        }
+
+        /// Is the location addressable within this grid?
+        pub fn contains(&self, location: &Location) -> bool {
+            location.0 < self.width && location.1 < self.height
+        }
+
         pub fn _pretty_print(&self) {
             for strand in self.grid.iter() {
                 for bulb in strand.iter() {
                     print!("{}", bulb);
                 }
-                println!("");
+                println!();
             }
         }
-        pub fn number_on(&self) -> u32 {
+
+        /// The total brightness of the grid.  This is a u64, since a
+        /// grid of bright enough bulbs overflows a u32.
+        pub fn number_on(&self) -> u64 {
             let mut count = 0;
             for strand in self.grid.iter() {
                 for bulb in strand.iter() {
-                    count = count + bulb;
+                    count += u64::from(*bulb);
                 }
             }
             count
@@ -87,10 +125,36 @@ pub mod lights {
 
     #[derive(Debug, PartialEq)]
     pub enum Instruction {
-        Invalid,
-        TurnOn { start: Location, end: Location },
-        TurnOff { start: Location, end: Location },
-        Toggle { start: Location, end: Location },
+        TurnOn {
+            start: Location,
+            end: Location,
+        },
+        TurnOff {
+            start: Location,
+            end: Location,
+        },
+        Toggle {
+            start: Location,
+            end: Location,
+        },
+        /// Set every bulb in the range to exactly `level`.
+        Set {
+            level: u32,
+            start: Location,
+            end: Location,
+        },
+        /// Lower every bulb in the range by `amount`, stopping at zero.
+        Dim {
+            amount: u32,
+            start: Location,
+            end: Location,
+        },
+        /// Turn every dark bulb in the range to brightness 1 and every
+        /// lit bulb to 0.
+        Invert {
+            start: Location,
+            end: Location,
+        },
     }
 
     pub fn iterate_on_subset(
@@ -105,15 +169,68 @@ pub mod lights {
                     BulbAction::TurnOn => *light_bulb = light_bulb.saturating_add(1), //BulbValue::On,
                     BulbAction::TurnOff => *light_bulb = light_bulb.saturating_sub(1), //BulbValue::Off,
                     BulbAction::Toggle => *light_bulb = light_bulb.saturating_add(2),
+                    BulbAction::Set(level) => *light_bulb = level,
+                    BulbAction::Dim(amount) => *light_bulb = light_bulb.saturating_sub(amount),
+                    BulbAction::Invert => *light_bulb = if *light_bulb == 0 { 1 } else { 0 },
+                }
+            }
+        }
+    }
+
+    /// The ways a line of input can fail to be an instruction.  Each
+    /// variant carries the (1-based) line number it was found on.
+    #[derive(Debug, PartialEq)]
+    pub enum ParseError {
+        /// The line does not begin with one of the known verbs.
+        UnknownVerb { line: usize, text: String },
+        /// The brightness given to `set` or `dim` is not a number.
+        BadArgument { line: usize, text: String },
+        /// The text following the verb is not `x,y through x,y`.
+        BadCoordinate { line: usize, text: String },
+        /// The start of the range lies beyond its end.
+        InvertedRange {
+            line: usize,
+            start: Location,
+            end: Location,
+        },
+        /// A coordinate lies outside the light grid.
+        OutOfBounds { line: usize, location: Location },
+    }
+
+    impl fmt::Display for ParseError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ParseError::UnknownVerb { line, text } => {
+                    write!(f, "line {}: unknown instruction `{}`", line, text)
+                }
+                ParseError::BadArgument { line, text } => {
+                    write!(f, "line {}: bad brightness `{}`", line, text)
+                }
+                ParseError::BadCoordinate { line, text } => {
+                    write!(f, "line {}: bad coordinates `{}`", line, text)
+                }
+                ParseError::InvertedRange { line, start, end } => write!(
+                    f,
+                    "line {}: range start {} lies beyond its end {}",
+                    line, start, end
+                ),
+                ParseError::OutOfBounds { line, location } => {
+                    write!(
+                        f,
+                        "line {}: location {} is outside the grid",
+                        line, location
+                    )
                 }
             }
         }
     }
 
+    impl std::error::Error for ParseError {}
+
     /* Given an instruction and a light grid, execute the
      * instruction on the LightGrid by turning on, turning off
      * or toggling the light. */
-    pub fn execute(i: &Instruction, l: &mut LightGrid) -> bool {
+    pub fn execute(i: &Instruction, l: &mut LightGrid) {
         match i {
             Instruction::TurnOn { start: s, end: e } => {
                 iterate_on_subset(BulbAction::TurnOn, l, s, e);
@@ -124,29 +241,61 @@ pub mod lights {
             Instruction::Toggle { start: s, end: e } => {
                 iterate_on_subset(BulbAction::Toggle, l, s, e);
             }
-            _ => {
-                return false;
+            Instruction::Set {
+                level,
+                start: s,
+                end: e,
+            } => {
+                iterate_on_subset(BulbAction::Set(*level), l, s, e);
+            }
+            Instruction::Dim {
+                amount,
+                start: s,
+                end: e,
+            } => {
+                iterate_on_subset(BulbAction::Dim(*amount), l, s, e);
+            }
+            Instruction::Invert { start: s, end: e } => {
+                iterate_on_subset(BulbAction::Invert, l, s, e);
             }
         }
-        true
     }
 
     /* Given a string like `toggle 111,55 through 880,871`
-     * return a tuple comprising the Instruction, the Start
-     * Location and the End Location */
-    pub fn parse(s: &str) -> Instruction {
-        let re = Regex::new(
-            r"^(toggle|turn on|turn off) (\d{1,3}),(\d{1,3}) through (\d{1,3}),(\d{1,3})$",
-        )
-        .unwrap();
+     * found on the given line of input, return the Instruction
+     * comprising the action, the Start Location and the End
+     * Location.  Both locations must lie within the given grid and
+     * the Start must not lie beyond the End. */
+    pub fn parse(line: usize, s: &str, l: &LightGrid) -> Result<Instruction, ParseError> {
+        let verb = VERBS
+            .iter()
+            .find(|v| s.starts_with(*v) && s[v.len()..].starts_with(' '))
+            .ok_or_else(|| ParseError::UnknownVerb {
+                line,
+                text: s.to_string(),
+            })?;
+        let mut rest = &s[verb.len() + 1..];
 
-        if !re.is_match(&s) {
-            return Instruction::Invalid;
+        // `set` and `dim` carry a brightness ahead of the range.
+        let mut argument = 0;
+        if *verb == "set" || *verb == "dim" {
+            let (text, remainder) = rest.split_at(rest.find(' ').unwrap_or(rest.len()));
+            argument = text.parse::<u32>().map_err(|_| ParseError::BadArgument {
+                line,
+                text: text.to_string(),
+            })?;
+            rest = remainder.trim_start();
         }
 
-        let cap = re.captures(&s).unwrap();
+        let bad_coordinate = || ParseError::BadCoordinate {
+            line,
+            text: rest.to_string(),
+        };
+
+        let re = Regex::new(r"^(\d+),(\d+) through (\d+),(\d+)$").unwrap();
+        let cap = re.captures(rest).ok_or_else(bad_coordinate)?;
         if cap.len() != CG_TOTAL {
-            return Instruction::Invalid;
+            return Err(bad_coordinate());
         }
 
         let mut coordinates = Vec::new();
@@ -154,24 +303,47 @@ pub mod lights {
             let orig = &cap[i];
             match orig.parse::<usize>() {
                 Ok(coordinate) => coordinates.push(coordinate),
-                Err(_) => return Instruction::Invalid,
+                Err(_) => return Err(bad_coordinate()),
             }
         }
 
         let start = Location(coordinates[0], coordinates[1]);
         let end = Location(coordinates[2], coordinates[3]);
 
-        match &cap[CG_INSTRUCTION] {
-            "toggle" => return Instruction::Toggle { start, end },
-            "turn on" => return Instruction::TurnOn { start, end },
-            "turn off" => return Instruction::TurnOff { start, end },
-            _ => return Instruction::Invalid,
+        for location in [&start, &end].iter() {
+            if !l.contains(location) {
+                return Err(ParseError::OutOfBounds {
+                    line,
+                    location: Location(location.0, location.1),
+                });
+            }
+        }
+
+        if start.0 > end.0 || start.1 > end.1 {
+            return Err(ParseError::InvertedRange { line, start, end });
+        }
+
+        match *verb {
+            "toggle" => Ok(Instruction::Toggle { start, end }),
+            "turn on" => Ok(Instruction::TurnOn { start, end }),
+            "turn off" => Ok(Instruction::TurnOff { start, end }),
+            "set" => Ok(Instruction::Set {
+                level: argument,
+                start,
+                end,
+            }),
+            "dim" => Ok(Instruction::Dim {
+                amount: argument,
+                start,
+                end,
+            }),
+            _ => Ok(Instruction::Invert { start, end }),
         }
     }
 
     // Tests
-    fn test_parse(s: &str, expected: Instruction) -> bool {
-        if parse(&s) == expected {
+    fn test_parse(s: &str, expected: Result<Instruction, ParseError>) -> bool {
+        if parse(1, s, &LightGrid::new(GRID_SIZE, GRID_SIZE)) == expected {
             return true;
         }
         false
@@ -180,21 +352,129 @@ pub mod lights {
     pub fn test_lights() {
         assert!(test_parse(
             "definitely not an instruction",
-            Instruction::Invalid
+            Err(ParseError::UnknownVerb {
+                line: 1,
+                text: "definitely not an instruction".to_string()
+            })
         ));
         assert!(test_parse(
             "toggle 111,55 through 880,871",
-            Instruction::Toggle {
+            Ok(Instruction::Toggle {
                 start: Location(111, 55),
                 end: Location(880, 871)
-            }
+            })
         ));
         assert!(test_parse(
             "turn off 111,55 through 880,871",
-            Instruction::TurnOff {
+            Ok(Instruction::TurnOff {
                 start: Location(111, 55),
                 end: Location(880, 871)
-            }
+            })
+        ));
+        assert!(test_parse(
+            "turn offf 111,55 through 880,871",
+            Err(ParseError::UnknownVerb {
+                line: 1,
+                text: "turn offf 111,55 through 880,871".to_string()
+            })
+        ));
+        assert!(test_parse(
+            "toggle 111,x through 880,871",
+            Err(ParseError::BadCoordinate {
+                line: 1,
+                text: "111,x through 880,871".to_string()
+            })
+        ));
+        assert!(test_parse(
+            "toggle 880,55 through 111,871",
+            Err(ParseError::InvertedRange {
+                line: 1,
+                start: Location(880, 55),
+                end: Location(111, 871)
+            })
+        ));
+        assert!(test_parse(
+            "turn on 0,0 through 999,1000",
+            Err(ParseError::OutOfBounds {
+                line: 1,
+                location: Location(999, 1000)
+            })
+        ));
+        assert!(test_parse(
+            "turn on 0,0 through 99999999999999999999,0",
+            Err(ParseError::BadCoordinate {
+                line: 1,
+                text: "0,0 through 99999999999999999999,0".to_string()
+            })
+        ));
+        assert!(test_parse(
+            "turn on 0,0 through 1500,2",
+            Err(ParseError::OutOfBounds {
+                line: 1,
+                location: Location(1500, 2)
+            })
+        ));
+        assert!(test_parse(
+            "set 7 0,0 through 9,9",
+            Ok(Instruction::Set {
+                level: 7,
+                start: Location(0, 0),
+                end: Location(9, 9)
+            })
+        ));
+        assert!(test_parse(
+            "dim 2 3,4 through 5,6",
+            Ok(Instruction::Dim {
+                amount: 2,
+                start: Location(3, 4),
+                end: Location(5, 6)
+            })
         ));
+        assert!(test_parse(
+            "invert 0,0 through 0,0",
+            Ok(Instruction::Invert {
+                start: Location(0, 0),
+                end: Location(0, 0)
+            })
+        ));
+        assert!(test_parse(
+            "set bright 0,0 through 9,9",
+            Err(ParseError::BadArgument {
+                line: 1,
+                text: "bright".to_string()
+            })
+        ));
+
+        // A small grid rejects what the puzzle's grid accepts.
+        let mut small = LightGrid::new(4, 2);
+        assert!(
+            parse(1, "toggle 0,0 through 3,2", &small)
+                == Err(ParseError::OutOfBounds {
+                    line: 1,
+                    location: Location(3, 2)
+                })
+        );
+
+        // Each verb of the dialect on a small grid.
+        for s in [
+            "set 5 0,0 through 3,1",
+            "dim 2 0,0 through 1,1",
+            "invert 3,0 through 3,1",
+            "turn off 2,0 through 2,0",
+            "toggle 0,1 through 0,1",
+        ]
+        .iter()
+        {
+            execute(&parse(1, s, &small).unwrap(), &mut small);
+        }
+        assert!(small.grid == vec![vec![3, 5], vec![3, 3], vec![4, 5], vec![0, 0]]);
+        assert!(small.number_on() == 23);
+
+        // Brightness past a u32 in total.
+        execute(
+            &parse(1, "set 4000000000 0,0 through 1,0", &small).unwrap(),
+            &mut small,
+        );
+        assert!(small.number_on() == 8_000_000_017);
     }
 }
@@ -1,24 +1,74 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use std::env;
 use std::fs;
 
+#[allow(clippy::module_inception)]
 mod lights;
 
+/* Given a size like `1000x1000`, return the width and height. */
+fn parse_size(s: &str) -> Result<(usize, usize)> {
+    let (w, h) = s
+        .split_once('x')
+        .ok_or_else(|| anyhow!("grid size `{}` is not WIDTHxHEIGHT", s))?;
+    Ok((w.parse()?, h.parse()?))
+}
+
+/* Usage: aoc [--size WIDTHxHEIGHT] [SCRIPT]
+ *
+ * By default the puzzle's 1000x1000 grid runs `input.txt`. */
 fn main() -> Result<()> {
     crate::lights::lights::test_lights();
 
-    let s = fs::read_to_string("input.txt")?;
-    let s = s.trim();
+    let mut size = (
+        crate::lights::lights::GRID_SIZE,
+        crate::lights::lights::GRID_SIZE,
+    );
+    let mut path = String::from("input.txt");
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--size" {
+            let value = args.next().ok_or_else(|| anyhow!("--size needs a value"))?;
+            size = parse_size(&value).with_context(|| format!("bad --size `{}`", value))?;
+        } else {
+            path = arg;
+        }
+    }
+
+    let s = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
+    let s = s.trim_end();
 
-    let mut decorations = crate::lights::lights::LightGrid {
-        grid: [[0; 1000]; 1000],
-    };
+    let mut decorations = crate::lights::lights::LightGrid::new(size.0, size.1);
+
+    // Parse every line before executing any of them, so that a
+    // malformed script leaves the grid untouched and every bad
+    // line is reported at once.
+    let mut instructions = Vec::new();
+    let mut errors = 0;
+    for (n, line) in s.split('\n').enumerate() {
+        // Leading and blank lines are skipped rather than trimmed away,
+        // so that errors report the line numbers of the file.
+        if line.trim().is_empty() {
+            continue;
+        }
+        match crate::lights::lights::parse(n + 1, line, &decorations) {
+            Ok(i) => instructions.push(i),
+            Err(e) => {
+                eprintln!("{}", e);
+                errors += 1;
+            }
+        }
+    }
+
+    if errors > 0 {
+        return Err(anyhow!("{} invalid instruction(s) in {}", errors, path));
+    }
 
-    for line in s.split('\n') {
-        let i = crate::lights::lights::parse(line);
-        crate::lights::lights::execute(&i, &mut decorations);
+    for i in instructions.iter() {
+        crate::lights::lights::execute(i, &mut decorations);
     }
 
-    println!("");
+    println!();
     println!("Number of Lights On: {}", decorations.number_on());
 
     Ok(())
@@ -0,0 +1,328 @@
+use crate::eval;
+use crate::types::{Assignment, Exp, State, Word};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A circuit is the set of assignments driving its wires, kept
+/// alongside an order in which they may be evaluated so that every
+/// wire's inputs are known before the wire itself.
+#[derive(Debug)]
+pub struct Circuit<W = u16> {
+    assignments: Vec<Assignment<W>>,
+    drivers: HashMap<String, usize>,
+    order: Vec<usize>,
+}
+
+/// The reasons a set of assignments cannot be evaluated.
+#[derive(Debug, PartialEq)]
+pub enum CircuitError {
+    /// More than one assignment drives the wire.
+    MultipleDrivers(String),
+    /// These wires are read by some expression, but no assignment
+    /// drives them.
+    Undriven(Vec<String>),
+    /// These wires depend on each other in a loop, listed in the
+    /// order that each one feeds the next.
+    Cycle(Vec<String>),
+    /// No assignment in the circuit drives the wire.
+    UnknownWire(String),
+}
+
+impl fmt::Display for CircuitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitError::MultipleDrivers(wire) => {
+                write!(f, "wire {} is driven more than once", wire)
+            }
+            CircuitError::Undriven(wires) => {
+                write!(f, "wires with no driver: {}", wires.join(", "))
+            }
+            CircuitError::Cycle(wires) => {
+                write!(
+                    f,
+                    "wires form a cycle: {} -> {}",
+                    wires.join(" -> "),
+                    wires[0]
+                )
+            }
+            CircuitError::UnknownWire(wire) => write!(f, "no such wire {}", wire),
+        }
+    }
+}
+
+impl std::error::Error for CircuitError {}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Unvisited,
+    InProgress,
+    Done,
+}
+
+impl<W: Word> Circuit<W> {
+    /// Build the circuit graph from its assignments and determine an
+    /// evaluation order for it.
+    pub fn new(assignments: Vec<Assignment<W>>) -> Result<Self, CircuitError> {
+        let mut drivers = HashMap::new();
+        for (i, a) in assignments.iter().enumerate() {
+            if drivers.insert(a.id.to_owned(), i).is_some() {
+                return Err(CircuitError::MultipleDrivers(a.id.to_owned()));
+            }
+        }
+
+        let mut undriven: Vec<String> = assignments
+            .iter()
+            .flat_map(|a| a.exp.inputs())
+            .filter(|v| !drivers.contains_key(*v))
+            .map(|v| v.to_owned())
+            .collect();
+        if !undriven.is_empty() {
+            undriven.sort();
+            undriven.dedup();
+            return Err(CircuitError::Undriven(undriven));
+        }
+
+        let mut circuit = Circuit {
+            assignments,
+            drivers,
+            order: Vec::new(),
+        };
+
+        let mut marks = vec![Mark::Unvisited; circuit.assignments.len()];
+        let mut path = Vec::new();
+        let mut order = Vec::new();
+        for i in 0..circuit.assignments.len() {
+            circuit.visit(i, &mut marks, &mut path, &mut order)?;
+        }
+        circuit.order = order;
+
+        Ok(circuit)
+    }
+
+    // Depth-first search from the assignment at index i, appending it
+    // to the order once all of its inputs have been.  `path` holds
+    // the assignments currently being visited; reaching one of them
+    // again means the circuit loops back on itself.
+    fn visit(
+        &self,
+        i: usize,
+        marks: &mut Vec<Mark>,
+        path: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<(), CircuitError> {
+        match marks[i] {
+            Mark::Done => return Ok(()),
+            Mark::InProgress => {
+                let start = path.iter().position(|p| *p == i).unwrap();
+                let wires = path[start..]
+                    .iter()
+                    .map(|p| self.assignments[*p].id.to_owned())
+                    .collect();
+                return Err(CircuitError::Cycle(wires));
+            }
+            Mark::Unvisited => {}
+        }
+
+        marks[i] = Mark::InProgress;
+        path.push(i);
+        for input in self.assignments[i].exp.inputs() {
+            self.visit(self.drivers[input], marks, path, order)?;
+        }
+        path.pop();
+        marks[i] = Mark::Done;
+        order.push(i);
+
+        Ok(())
+    }
+
+    /// Replace whatever drives the wire with the constant value.  The
+    /// override takes effect at the next evaluation.
+    pub fn override_wire(&mut self, wire: &str, value: W) -> Result<(), CircuitError> {
+        let i = *self
+            .drivers
+            .get(wire)
+            .ok_or_else(|| CircuitError::UnknownWire(wire.to_owned()))?;
+
+        // A literal reads no other wire, so the evaluation order
+        // computed for the original driver remains valid.
+        self.assignments[i].exp = Exp::Literal(value);
+        self.assignments[i].val = None;
+
+        Ok(())
+    }
+
+    /// The assignments of the circuit, in evaluation order.
+    pub fn assignments(&self) -> Vec<&Assignment<W>> {
+        self.order.iter().map(|i| &self.assignments[*i]).collect()
+    }
+
+    /// The assignments the wire's value depends upon, including its
+    /// own, in evaluation order.
+    pub fn fan_in(&self, wire: &str) -> Result<Vec<&Assignment<W>>, CircuitError> {
+        let start = *self
+            .drivers
+            .get(wire)
+            .ok_or_else(|| CircuitError::UnknownWire(wire.to_owned()))?;
+
+        let mut reached = HashSet::new();
+        let mut pending = vec![start];
+        while let Some(i) = pending.pop() {
+            if reached.insert(i) {
+                for input in self.assignments[i].exp.inputs() {
+                    pending.push(self.drivers[input]);
+                }
+            }
+        }
+
+        Ok(self
+            .order
+            .iter()
+            .filter(|i| reached.contains(*i))
+            .map(|i| &self.assignments[*i])
+            .collect())
+    }
+
+    /// The wire's value as of the last evaluation.
+    pub fn value(&self, wire: &str) -> Option<W> {
+        self.drivers
+            .get(wire)
+            .and_then(|i| self.assignments[*i].val)
+    }
+
+    /// Evaluate every wire in the circuit from a clean state, returning
+    /// the resulting program state.
+    pub fn evaluate(&mut self) -> State<W> {
+        let mut state = State::new();
+
+        for i in self.order.iter() {
+            let assign = &mut self.assignments[*i];
+            assign.val = None;
+            let evaluated = eval(assign, &mut state);
+            debug_assert!(evaluated, "{} evaluated out of order", assign.id);
+        }
+
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Circuit, CircuitError};
+    use crate::parse::parse;
+
+    fn circuit(lines: &[&str]) -> Result<Circuit, CircuitError> {
+        Circuit::new(lines.iter().map(|l| *parse(l).unwrap()).collect())
+    }
+
+    #[test]
+    fn evaluate_out_of_order() {
+        let mut c = circuit(&[
+            "x AND y -> d",
+            "x OR y -> e",
+            "x LSHIFT 2 -> f",
+            "y RSHIFT 2 -> g",
+            "NOT x -> h",
+            "NOT y -> i",
+            "123 -> x",
+            "456 -> y",
+        ])
+        .unwrap();
+        let state = c.evaluate();
+
+        assert_eq!(state.known.get("d"), Some(&72));
+        assert_eq!(state.known.get("e"), Some(&507));
+        assert_eq!(state.known.get("f"), Some(&492));
+        assert_eq!(state.known.get("g"), Some(&114));
+        assert_eq!(state.known.get("h"), Some(&65412));
+        assert_eq!(state.known.get("i"), Some(&65079));
+        assert_eq!(state.known.get("x"), Some(&123));
+        assert_eq!(state.known.get("y"), Some(&456));
+    }
+
+    #[test]
+    fn override_and_reevaluate() {
+        let mut c = circuit(&["b AND 6 -> a", "NOT c -> b", "65534 -> c"]).unwrap();
+        c.evaluate();
+        assert_eq!(c.value("a"), Some(0));
+
+        // Forcing b leaves the rest of the circuit to be recomputed
+        // from scratch, including the wires that were known before.
+        let a = c.value("a").unwrap();
+        c.override_wire("b", a).unwrap();
+        let state = c.evaluate();
+        assert_eq!(state.known.get("b"), Some(&0));
+        assert_eq!(state.known.get("c"), Some(&65534));
+        assert_eq!(c.value("a"), Some(0));
+
+        c.override_wire("b", 7).unwrap();
+        c.evaluate();
+        assert_eq!(c.value("a"), Some(6));
+
+        assert_eq!(
+            c.override_wire("z", 1).unwrap_err(),
+            CircuitError::UnknownWire("z".to_owned())
+        );
+    }
+
+    #[test]
+    fn fan_in() {
+        let c = circuit(&[
+            "x AND y -> d",
+            "NOT d -> e",
+            "y RSHIFT 2 -> g",
+            "123 -> x",
+            "456 -> y",
+        ])
+        .unwrap();
+
+        let wires: Vec<&str> = c
+            .fan_in("e")
+            .unwrap()
+            .iter()
+            .map(|a| a.id.as_str())
+            .collect();
+        assert_eq!(wires, vec!["x", "y", "d", "e"]);
+
+        let wires: Vec<&str> = c
+            .fan_in("x")
+            .unwrap()
+            .iter()
+            .map(|a| a.id.as_str())
+            .collect();
+        assert_eq!(wires, vec!["x"]);
+
+        assert_eq!(
+            c.fan_in("q").unwrap_err(),
+            CircuitError::UnknownWire("q".to_owned())
+        );
+    }
+
+    #[test]
+    fn report_cycles() {
+        assert_eq!(
+            circuit(&["1 -> a", "a AND c -> b", "b -> c"]).unwrap_err(),
+            CircuitError::Cycle(vec!["b".to_owned(), "c".to_owned()])
+        );
+        assert_eq!(
+            circuit(&["NOT x -> x"]).unwrap_err(),
+            CircuitError::Cycle(vec!["x".to_owned()])
+        );
+    }
+
+    #[test]
+    fn report_undriven_wires() {
+        assert_eq!(
+            circuit(&["q OR p -> b", "NOT q -> c", "1 -> a"]).unwrap_err(),
+            CircuitError::Undriven(vec!["p".to_owned(), "q".to_owned()])
+        );
+    }
+
+    #[test]
+    fn report_multiple_drivers() {
+        assert_eq!(
+            circuit(&["1 -> a", "2 -> a"]).unwrap_err(),
+            CircuitError::MultipleDrivers("a".to_owned())
+        );
+    }
+}
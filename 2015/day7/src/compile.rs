@@ -0,0 +1,199 @@
+use crate::circuit::Circuit;
+use crate::types::{BinaryOp, Exp, Term, UnaryOp, Word};
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Where an instruction reads a value from: the register holding
+/// another wire, or a constant.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand<W> {
+    Slot(usize),
+    Const(W),
+}
+
+/// An instruction computes the value of a single wire.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instr<W> {
+    Copy(Operand<W>),
+    Unary(UnaryOp, Operand<W>),
+    Binary(BinaryOp, Operand<W>, Operand<W>),
+}
+
+/// An override named a register the program does not have.
+#[derive(Debug, PartialEq)]
+pub struct SlotOutOfRange {
+    pub slot: usize,
+    pub slots: usize,
+}
+
+impl fmt::Display for SlotOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "no register {}, the program has {}",
+            self.slot, self.slots
+        )
+    }
+}
+
+impl std::error::Error for SlotOutOfRange {}
+
+/// A circuit compiled for a register machine.  The program holds one
+/// instruction per wire, in evaluation order, and the i-th instruction
+/// writes the i-th register; so running the program is a single pass
+/// over the instructions with no name lookups.
+#[derive(Debug)]
+pub struct Program<W = u16> {
+    code: Vec<Instr<W>>,
+    slots: HashMap<String, usize>,
+}
+
+impl<W: Word> Program<W> {
+    /// Compile the circuit into a program.
+    pub fn compile(circuit: &Circuit<W>) -> Self {
+        let assignments = circuit.assignments();
+        let slots: HashMap<String, usize> = assignments
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a.id.to_owned(), i))
+            .collect();
+
+        let operand = |t: &Term<W>| match t {
+            Term::Literal(l) => Operand::Const(*l),
+            Term::Variable(v) => Operand::Slot(slots[v]),
+        };
+
+        let code = assignments
+            .iter()
+            .map(|a| match &a.exp {
+                Exp::Literal(l) => Instr::Copy(Operand::Const(*l)),
+                Exp::Variable(v) => Instr::Copy(Operand::Slot(slots[v])),
+                Exp::UnaryExp(op, t) => Instr::Unary(*op, operand(t)),
+                Exp::BinaryExp(op, t1, t2) => Instr::Binary(*op, operand(t1), operand(t2)),
+            })
+            .collect();
+
+        Program { code, slots }
+    }
+
+    /// The register holding the wire's value.
+    pub fn slot(&self, wire: &str) -> Option<usize> {
+        self.slots.get(wire).copied()
+    }
+
+    /// Run the program, leaving each wire's value in its register.
+    /// Each override, a register and a value, replaces the instruction
+    /// computing that register with the constant value; where a
+    /// register is overridden more than once, the last value wins.
+    pub fn run(
+        &self,
+        overrides: &[(usize, W)],
+        registers: &mut Vec<W>,
+    ) -> Result<(), SlotOutOfRange> {
+        let mut fixed = vec![None; self.code.len()];
+        for (slot, value) in overrides.iter() {
+            *fixed.get_mut(*slot).ok_or(SlotOutOfRange {
+                slot: *slot,
+                slots: self.code.len(),
+            })? = Some(*value);
+        }
+
+        registers.clear();
+        registers.resize(self.code.len(), W::ZERO);
+
+        for (i, instr) in self.code.iter().enumerate() {
+            if let Some(value) = fixed[i] {
+                registers[i] = value;
+                continue;
+            }
+
+            let read = |o: Operand<W>| match o {
+                Operand::Slot(s) => registers[s],
+                Operand::Const(c) => c,
+            };
+            registers[i] = match *instr {
+                Instr::Copy(a) => read(a),
+                Instr::Unary(op, a) => op.apply(read(a)),
+                Instr::Binary(op, a, b) => op.apply(read(a), read(b)),
+            };
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Instr, Operand, Program, SlotOutOfRange};
+    use crate::circuit::Circuit;
+    use crate::parse::parse;
+    use crate::types::{BinaryOp, UnaryOp};
+
+    fn circuit(lines: &[&str]) -> Circuit {
+        Circuit::new(lines.iter().map(|l| *parse(l).unwrap()).collect()).unwrap()
+    }
+
+    #[test]
+    fn compile_in_evaluation_order() {
+        let c = circuit(&["x AND 3 -> d", "NOT d -> e", "123 -> x"]);
+        let p = Program::compile(&c);
+
+        assert_eq!(
+            p.code,
+            vec![
+                Instr::Copy(Operand::Const(123)),
+                Instr::Binary(BinaryOp::And, Operand::Slot(0), Operand::Const(3)),
+                Instr::Unary(UnaryOp::Not, Operand::Slot(1)),
+            ]
+        );
+        assert_eq!(p.slot("x"), Some(0));
+        assert_eq!(p.slot("e"), Some(2));
+        assert_eq!(p.slot("q"), None);
+
+        let mut registers = Vec::new();
+        p.run(&[], &mut registers).unwrap();
+        assert_eq!(registers, vec![123, 3, 65532]);
+
+        p.run(&[(0, 4)], &mut registers).unwrap();
+        assert_eq!(registers, vec![4, 0, 65535]);
+
+        p.run(&[(2, 9), (1, 1)], &mut registers).unwrap();
+        assert_eq!(registers, vec![123, 1, 9]);
+    }
+
+    #[test]
+    fn repeated_and_missing_overrides() {
+        let c = circuit(&["x AND 3 -> d", "NOT d -> e", "123 -> x"]);
+        let p = Program::compile(&c);
+        let mut registers = Vec::new();
+
+        // A register overridden twice takes the last value, and does
+        // not stop later registers being overridden.
+        p.run(&[(0, 4), (0, 7), (2, 9)], &mut registers).unwrap();
+        assert_eq!(registers, vec![7, 3, 9]);
+
+        assert_eq!(
+            p.run(&[(1, 1), (3, 5)], &mut registers),
+            Err(SlotOutOfRange { slot: 3, slots: 3 })
+        );
+    }
+
+    #[test]
+    fn compiled_matches_circuit() {
+        let lines: Vec<&str> = include_str!("../input.txt").trim().split('\n').collect();
+        let mut c = circuit(&lines);
+        let p = Program::compile(&c);
+        let b = p.slot("b").unwrap();
+        let mut registers = Vec::new();
+
+        for value in [0, 1, 3176, 16076, 65535].iter() {
+            c.override_wire("b", *value).unwrap();
+            c.evaluate();
+            p.run(&[(b, *value)], &mut registers).unwrap();
+            for a in c.assignments() {
+                assert_eq!(a.val, Some(registers[p.slot(&a.id).unwrap()]));
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::circuit::{Circuit, CircuitError};
+use crate::types::{Assignment, Exp, Term, Word};
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+// The label for a wire's edge, annotated with its value once known.
+fn wire_label<W: Word>(a: &Assignment<W>) -> String {
+    match a.val {
+        Some(v) => format!("{} = {}", a.id, v),
+        None => a.id.to_owned(),
+    }
+}
+
+/// Render the circuit as a Graphviz DOT graph.  Each assignment is a
+/// gate node labelled by its operator, and each wire is an edge from
+/// the gate driving it to every gate reading it.  Wires no gate reads
+/// end at a node of their own name.  When `wire` is given, only the
+/// gates the wire's value depends upon are drawn.
+pub fn to_dot<W: Word>(circuit: &Circuit<W>, wire: Option<&str>) -> Result<String, CircuitError> {
+    let assignments = match wire {
+        Some(w) => circuit.fan_in(w)?,
+        None => circuit.assignments(),
+    };
+    let labels: HashMap<&str, String> = assignments
+        .iter()
+        .map(|a| (a.id.as_str(), wire_label(a)))
+        .collect();
+
+    let mut read = HashSet::new();
+    let mut dot = String::new();
+    writeln!(dot, "digraph circuit {{").unwrap();
+    writeln!(dot, "    rankdir=LR;").unwrap();
+
+    for a in assignments.iter() {
+        let gate = format!("gate_{}", a.id);
+        let (name, terms) = match &a.exp {
+            Exp::Literal(l) => (l.to_string(), vec![]),
+            Exp::Variable(v) => ("BUF".to_owned(), vec![Term::Variable(v.to_owned())]),
+            Exp::UnaryExp(op, t) => (op.to_string(), vec![t.clone()]),
+            Exp::BinaryExp(op, t1, t2) => (op.to_string(), vec![t1.clone(), t2.clone()]),
+        };
+        writeln!(dot, "    \"{}\" [shape=box, label=\"{}\"];", gate, name).unwrap();
+
+        for (n, t) in terms.iter().enumerate() {
+            match t {
+                Term::Variable(v) => {
+                    read.insert(v.to_owned());
+                    writeln!(
+                        dot,
+                        "    \"gate_{}\" -> \"{}\" [label=\"{}\"];",
+                        v,
+                        gate,
+                        labels[v.as_str()]
+                    )
+                    .unwrap();
+                }
+                Term::Literal(l) => {
+                    let constant = format!("const_{}_{}", a.id, n);
+                    writeln!(
+                        dot,
+                        "    \"{}\" [shape=plaintext, label=\"{}\"];",
+                        constant, l
+                    )
+                    .unwrap();
+                    writeln!(dot, "    \"{}\" -> \"{}\";", constant, gate).unwrap();
+                }
+            }
+        }
+    }
+
+    for a in assignments.iter() {
+        if !read.contains(&a.id) {
+            writeln!(
+                dot,
+                "    \"wire_{}\" [shape=plaintext, label=\"{}\"];",
+                a.id, a.id
+            )
+            .unwrap();
+            writeln!(
+                dot,
+                "    \"gate_{}\" -> \"wire_{}\" [label=\"{}\"];",
+                a.id,
+                a.id,
+                labels[a.id.as_str()]
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(dot, "}}").unwrap();
+    Ok(dot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_dot;
+    use crate::circuit::Circuit;
+    use crate::parse::parse;
+
+    fn circuit(lines: &[&str]) -> Circuit {
+        Circuit::new(lines.iter().map(|l| *parse(l).unwrap()).collect()).unwrap()
+    }
+
+    #[test]
+    fn dot_gates_and_wires() {
+        let mut c = circuit(&["x AND 3 -> d", "NOT x -> e", "123 -> x"]);
+        let dot = to_dot(&c, None).unwrap();
+
+        assert!(dot.starts_with("digraph circuit {\n"));
+        assert!(dot.contains("\"gate_x\" [shape=box, label=\"123\"];"));
+        assert!(dot.contains("\"gate_d\" [shape=box, label=\"AND\"];"));
+        assert!(dot.contains("\"gate_e\" [shape=box, label=\"NOT\"];"));
+        assert!(dot.contains("\"gate_x\" -> \"gate_d\" [label=\"x\"];"));
+        assert!(dot.contains("\"const_d_1\" -> \"gate_d\";"));
+        assert!(dot.contains("\"gate_d\" -> \"wire_d\" [label=\"d\"];"));
+        assert!(dot.contains("\"gate_e\" -> \"wire_e\" [label=\"e\"];"));
+        assert!(!dot.contains("wire_x"));
+        assert!(dot.ends_with("}\n"));
+
+        c.evaluate();
+        let dot = to_dot(&c, None).unwrap();
+        assert!(dot.contains("\"gate_x\" -> \"gate_d\" [label=\"x = 123\"];"));
+        assert!(dot.contains("\"gate_d\" -> \"wire_d\" [label=\"d = 3\"];"));
+    }
+
+    #[test]
+    fn dot_fan_in() {
+        let c = circuit(&["x AND 3 -> d", "NOT x -> e", "123 -> x", "NOT e -> f"]);
+        let dot = to_dot(&c, Some("d")).unwrap();
+
+        assert!(dot.contains("\"gate_d\""));
+        assert!(dot.contains("\"gate_x\" -> \"gate_d\" [label=\"x\"];"));
+        assert!(!dot.contains("gate_e"));
+        assert!(!dot.contains("gate_f"));
+        assert!(to_dot(&c, Some("q")).is_err());
+    }
+}
@@ -1,23 +1,35 @@
+mod circuit;
+mod compile;
+mod dot;
 mod parse;
+mod repl;
+mod simplify;
 mod types;
 
-use self::parse::parse;
-use self::types::{Assignment, Exp, State, Term};
+use self::circuit::Circuit;
+use self::compile::Program;
+use self::dot::to_dot;
+use self::parse::parse_program;
+use self::simplify::simplify;
+use self::types::{Assignment, Exp, State, Term, Word};
 
+use anyhow::{anyhow, Context, Result};
 use std::collections::HashMap;
+use std::env;
 use std::fs;
+use std::time::Instant;
 
 // Return true if a new valuation was created.
-fn eval<'a>(assign: &'a mut Assignment, state: &'a mut State) -> bool {
+fn eval<'a, W: Word>(assign: &'a mut Assignment<W>, state: &'a mut State<W>) -> bool {
     // An assignment have have been previously evaluated.  If so, its
     // valuation is available in the assignment's val field.
-    if let Some(_) = assign.val {
+    if assign.val.is_some() {
         return false;
     }
 
     // Attempt to evaluate the expression. If expression evaluation
     // returns Some(_) then we add it to known program state.
-    let maybe_evaluated_expr: Option<u16> = eval_expr(&assign.exp, &state.known);
+    let maybe_evaluated_expr: Option<W> = eval_expr(&assign.exp, &state.known);
 
     // The expression has only Literals and may be immediately
     // evaluated.
@@ -28,91 +40,225 @@ fn eval<'a>(assign: &'a mut Assignment, state: &'a mut State) -> bool {
     }
 
     // This assignment cannot be evaluated given the known state.
-    return false;
+    false
 }
 
-fn eval_expr(exp: &Exp, known: &HashMap<String, u16>) -> Option<u16> {
+fn eval_expr<W: Word>(exp: &Exp<W>, known: &HashMap<String, W>) -> Option<W> {
     match exp {
         Exp::Literal(el) => Some(*el),
-        Exp::UnaryExp(f, Term::Literal(el)) => Some(f(*el)),
-        Exp::BinaryExp(f, Term::Literal(el1), Term::Literal(el2)) => Some(f(*el1, *el2)),
-        Exp::Variable(v) => {
-            let known_val = known.get(v);
-            if let Some(kv) = known_val {
-                return Some(*kv);
-            } else {
-                return None;
-            }
-        }
-        Exp::UnaryExp(f, Term::Variable(v)) => {
-            let known_val = known.get(v);
-            if let Some(kv) = known_val {
-                return Some(f(*kv));
-            } else {
-                return None;
-            }
+        Exp::UnaryExp(op, Term::Literal(el)) => Some(op.apply(*el)),
+        Exp::BinaryExp(op, Term::Literal(el1), Term::Literal(el2)) => Some(op.apply(*el1, *el2)),
+        Exp::Variable(v) => known.get(v).copied(),
+        Exp::UnaryExp(op, Term::Variable(v)) => known.get(v).map(|kv| op.apply(*kv)),
+        Exp::BinaryExp(op, Term::Literal(el), Term::Variable(v)) => {
+            known.get(v).map(|kv| op.apply(*el, *kv))
         }
-        Exp::BinaryExp(f, Term::Literal(el), Term::Variable(v)) => {
-            let known_val = known.get(v);
-            if let Some(kv) = known_val {
-                return Some(f(*el, *kv));
-            } else {
-                return None;
-            }
+        Exp::BinaryExp(op, Term::Variable(v), Term::Literal(el)) => {
+            known.get(v).map(|kv| op.apply(*kv, *el))
         }
-        Exp::BinaryExp(f, Term::Variable(v), Term::Literal(el)) => {
-            let known_val = known.get(v);
-            if let Some(kv) = known_val {
-                return Some(f(*el, *kv));
-            } else {
-                return None;
-            }
-        }
-        Exp::BinaryExp(f, Term::Variable(v1), Term::Variable(v2)) => {
+        Exp::BinaryExp(op, Term::Variable(v1), Term::Variable(v2)) => {
             let known_val1 = known.get(v1);
             let known_val2 = known.get(v2);
             let pair = (known_val1, known_val2);
 
             match pair {
-                (Some(kv1), Some(kv2)) => Some(f(*kv1, *kv2)),
+                (Some(kv1), Some(kv2)) => Some(op.apply(*kv1, *kv2)),
                 _ => None,
             }
         }
     }
 }
 
-fn main() {
-    let mut assignments = Vec::new();
-    let mut state = State::new();
+// Given an override like `b=3176`, return the wire and its value.
+fn parse_override<W: Word>(s: &str) -> Result<(String, W)> {
+    let (wire, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("override `{}` is not <wire>=<value>", s))?;
+    let value = value
+        .parse::<W>()
+        .map_err(|_| anyhow!("bad override value `{}` for {} bits", value, W::BITS))?;
+    Ok((wire.to_owned(), value))
+}
+
+// Evaluate wire `a` once for each value of wire `b` in 0..iterations,
+// first by re-evaluating the circuit and then by running it compiled,
+// and report how long each took.
+fn bench<W: Word>(circuit: &mut Circuit<W>, iterations: u64) -> Result<()> {
+    let program = Program::compile(circuit);
+    let a = program.slot("a").ok_or_else(|| anyhow!("no wire a"))?;
+    let b = program.slot("b").ok_or_else(|| anyhow!("no wire b"))?;
+
+    let start = Instant::now();
+    let mut evaluated = Vec::new();
+    for value in 0..iterations {
+        circuit.override_wire("b", W::truncate(value))?;
+        circuit.evaluate();
+        evaluated.push(circuit.value("a"));
+    }
+    let eval_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut registers = Vec::new();
+    let mut compiled = Vec::new();
+    for value in 0..iterations {
+        program.run(&[(b, W::truncate(value))], &mut registers)?;
+        compiled.push(Some(registers[a]));
+    }
+    let compiled_time = start.elapsed();
+
+    if evaluated != compiled {
+        return Err(anyhow!("compiled program disagrees with the circuit"));
+    }
+
+    println!("{} evaluations", iterations);
+    println!("  eval:     {:?}", eval_time);
+    println!("  compiled: {:?}", compiled_time);
+
+    Ok(())
+}
+
+// The command line, with override values still to be parsed at the
+// chosen width.
+#[derive(Default)]
+struct Options {
+    repl: bool,
+    file: Option<String>,
+    width: Option<u32>,
+    overrides: Vec<String>,
+    dot: bool,
+    reduce: bool,
+    iterations: Option<u64>,
+    fan_in: Option<String>,
+}
+
+fn run<W: Word>(options: &Options) -> Result<()> {
+    if options.repl {
+        return repl::run::<W>(options.file.as_deref());
+    }
 
-    let s = fs::read_to_string("input.txt").unwrap();
-    let s = s.trim();
+    // The benchmark drives wire `b` itself and prints only timings.
+    if options.iterations.is_some() {
+        if !options.overrides.is_empty() {
+            return Err(anyhow!("--bench cannot be combined with --override"));
+        }
+        if options.dot {
+            return Err(anyhow!("--bench cannot be combined with --dot"));
+        }
+        if options.fan_in.is_some() {
+            return Err(anyhow!("--bench always reads wire a, so takes no --fan-in"));
+        }
+    }
+    if options.fan_in.is_some() && !options.dot && !options.reduce {
+        return Err(anyhow!("--fan-in needs --dot or --simplify"));
+    }
 
-    // Gather and parse all the assignments in the input.
-    for line in s.split('\n') {
-        let assignment = parse(line).unwrap();
-        assignments.push(assignment);
+    let mut overrides = Vec::new();
+    for o in options.overrides.iter() {
+        overrides.push(parse_override::<W>(o)?);
     }
 
-    let mut last_evaluations;
-    let mut this_evaluations;
+    let path = options.file.as_deref().unwrap_or("input.txt");
+    let s = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
 
-    // Evaluate assignments until no new assignments may
-    // be evaluated.
-    loop {
-        this_evaluations = 0;
-        for a in assignments.iter_mut() {
-            if eval(a, &mut state) {
-                this_evaluations += 1;
+    // Gather and parse all the assignments in the input, reporting
+    // every line that is not one.
+    let assignments = match parse_program(&s) {
+        Ok(assignments) => assignments,
+        Err(errors) => {
+            for e in errors.iter() {
+                eprintln!("{}: {}", path, e);
             }
+            return Err(anyhow!("{} syntax error(s) in {}", errors.len(), path));
         }
-        last_evaluations = this_evaluations;
-        if this_evaluations == 0 && last_evaluations == 0 {
-            break;
+    };
+
+    // Order the assignments so that each wire is evaluated only
+    // after all of the wires it reads.
+    let mut circuit = Circuit::new(assignments)?;
+    if options.reduce {
+        let mut inputs: Vec<&str> = overrides.iter().map(|(w, _)| w.as_str()).collect();
+        // The benchmark overrides `b`, so it must survive as an input.
+        if options.iterations.is_some() {
+            inputs.push("b");
         }
+        let output = options.fan_in.as_deref().unwrap_or("a");
+        let simplified = simplify(&circuit, &inputs, &[output])?;
+        eprintln!("Simplification eliminated {} gates", simplified.eliminated);
+        circuit = simplified.circuit;
+    }
+    if let Some(iterations) = options.iterations {
+        return bench(&mut circuit, iterations);
     }
 
-    println!("The value of a: {:?}", state.known.get("a"));
+    for (wire, value) in overrides.iter() {
+        circuit.override_wire(wire, *value)?;
+    }
+    circuit.evaluate();
+
+    if options.dot {
+        print!("{}", to_dot(&circuit, options.fan_in.as_deref())?);
+        return Ok(());
+    }
+
+    println!("The value of a: {:?}", circuit.value("a"));
+
+    Ok(())
+}
+
+// Usage: day7 [--width 8|16|32|64] [--override <wire>=<value>]...
+//             [--simplify] [--dot [--fan-in <wire>]]
+//             [--bench <iterations>] [<file>]
+//        day7 circuit-repl [--width 8|16|32|64] [<file>]
+//
+// The circuit is read from input.txt unless another file is given,
+// and its wires are 16 bits wide unless another width is given.
+// With --dot the evaluated circuit is printed as a Graphviz graph,
+// limited to the gates feeding one wire when --fan-in is given.
+// With --simplify the circuit is first reduced to the gates feeding
+// that wire (or `a`), keeping any overridden wires as inputs.  With
+// --bench the circuit is evaluated repeatedly, overriding `b`, both
+// directly and compiled to a register machine program, so it takes no
+// --override, --dot or --fan-in.  The circuit-repl mode edits and
+// queries a circuit interactively.
+fn main() -> Result<()> {
+    let mut options = Options::default();
+
+    let mut args = env::args().skip(1).peekable();
+    if args.peek().map(|a| a.as_str()) == Some("circuit-repl") {
+        args.next();
+        options.repl = true;
+    }
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| anyhow!("{} needs a value", name));
+        match arg.as_str() {
+            "--width" => {
+                let v = value("--width")?;
+                options.width = Some(v.parse().with_context(|| format!("bad width `{}`", v))?);
+            }
+            "--override" => options.overrides.push(value("--override")?),
+            "--dot" => options.dot = true,
+            "--simplify" => options.reduce = true,
+            "--bench" => {
+                let v = value("--bench")?;
+                options.iterations = Some(
+                    v.parse()
+                        .with_context(|| format!("bad iterations `{}`", v))?,
+                );
+            }
+            "--fan-in" => options.fan_in = Some(value("--fan-in")?),
+            _ if !arg.starts_with("--") && options.file.is_none() => options.file = Some(arg),
+            _ => return Err(anyhow!("unexpected argument `{}`", arg)),
+        }
+    }
+
+    match options.width.unwrap_or(16) {
+        8 => run::<u8>(&options),
+        16 => run::<u16>(&options),
+        32 => run::<u32>(&options),
+        64 => run::<u64>(&options),
+        w => Err(anyhow!("unsupported width {}, expected 8, 16, 32 or 64", w)),
+    }
 }
 
 #[cfg(test)]
@@ -123,7 +269,7 @@ mod tests {
 
     #[test]
     fn eval_assignments() {
-        let mut state = State::new();
+        let mut state: State = State::new();
         let mut my_assign;
 
         my_assign = parse("1 -> x").unwrap();
@@ -155,7 +301,7 @@ mod tests {
     #[test]
     fn eval_expressions() {
         let mut my_assign;
-        let known = HashMap::new();
+        let known: HashMap<String, u16> = HashMap::new();
 
         my_assign = parse("1 AND 0 -> d").unwrap();
         assert_eq!(eval_expr(&my_assign.exp, &known), Some(0));
@@ -172,4 +318,26 @@ mod tests {
         my_assign = parse("0 OR 0 -> d").unwrap();
         assert_eq!(eval_expr(&my_assign.exp, &known), Some(0));
     }
+
+    #[test]
+    fn eval_operand_order() {
+        let mut my_assign;
+        let mut known: HashMap<String, u16> = HashMap::new();
+        known.insert("x".to_owned(), 3);
+
+        my_assign = parse("x LSHIFT 2 -> d").unwrap();
+        assert_eq!(eval_expr(&my_assign.exp, &known), Some(12));
+
+        my_assign = parse("2 LSHIFT x -> d").unwrap();
+        assert_eq!(eval_expr(&my_assign.exp, &known), Some(16));
+
+        my_assign = parse("x RSHIFT 1 -> d").unwrap();
+        assert_eq!(eval_expr(&my_assign.exp, &known), Some(1));
+
+        my_assign = parse("8 RSHIFT x -> d").unwrap();
+        assert_eq!(eval_expr(&my_assign.exp, &known), Some(1));
+
+        my_assign = parse("3 LSHIFT 2 -> d").unwrap();
+        assert_eq!(eval_expr(&my_assign.exp, &known), Some(12));
+    }
 }
@@ -1,114 +1,360 @@
-use crate::types::{Assignment, Exp, Term};
+use crate::types::{Assignment, BinaryOp, Exp, Term, UnaryOp, Word};
 
-use regex::Regex;
+use std::fmt;
 
-fn aoc_and(a: u16, b: u16) -> u16 {
-    return a & b;
+/// The ways a line fails to be an assignment.
+#[derive(Debug, PartialEq)]
+pub enum ErrorKind {
+    /// A character that cannot begin any token.
+    BadCharacter(char),
+    /// A token, or the end of the line, where something else was
+    /// expected.
+    Unexpected {
+        found: String,
+        expected: &'static str,
+    },
+    /// A literal is too large for the circuit's word.
+    Overflow { literal: String, bits: u32 },
+    /// A word that begins with a digit but is not all digits.
+    BadNumber(String),
 }
 
-fn aoc_or(a: u16, b: u16) -> u16 {
-    return a | b;
+/// A syntax error and where it was found.  Lines and columns count
+/// from 1.
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: ErrorKind,
 }
 
-fn aoc_not(a: u16) -> u16 {
-    return !a;
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            ErrorKind::BadCharacter(c) => write!(f, "unexpected character `{}`", c),
+            ErrorKind::Unexpected { found, expected } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ErrorKind::Overflow { literal, bits } => {
+                write!(f, "literal {} does not fit in {} bits", literal, bits)
+            }
+            ErrorKind::BadNumber(word) => write!(f, "`{}` is not a number", word),
+        }
+    }
 }
 
-fn aoc_lshift(a: u16, b: u16) -> u16 {
-    let shift = b.checked_shl(a.into());
-    match shift {
-        Some(v) => v,
-        None => 0,
-    }
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(String),
+    Ident(String),
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    Arrow,
+    // A word already reported as an error.
+    Invalid,
 }
 
-fn aoc_rshift(a: u16, b: u16) -> u16 {
-    let shift = b.checked_shr(a.into());
-    match shift {
-        Some(v) => v,
-        None => 0,
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "`{}`", n),
+            Token::Ident(i) => write!(f, "`{}`", i),
+            Token::Unary(op) => write!(f, "`{}`", op),
+            Token::Binary(op) => write!(f, "`{}`", op),
+            Token::Arrow => write!(f, "`->`"),
+            Token::Invalid => write!(f, "an invalid word"),
+        }
     }
 }
 
-/// Given a string representing a term in an expression return its
-/// type, either a Variable (like "x") or a Literal (like 1).
-fn reduce<'a>(s: &'a str) -> Term {
-    let term = s.to_string();
+/// Split a line into tokens, each paired with the column it starts at,
+/// and report every word that is not a token.  Words are separated by
+/// whitespace and `->`.  Numbers are runs of ASCII digits; identifiers
+/// begin with an ASCII letter or underscore and continue with ASCII
+/// letters, digits and underscores; the upper case operator names are
+/// reserved.  A `#` begins a comment running to the end of the line.
+fn tokenize(line: usize, s: &str) -> (Vec<(usize, Token)>, Vec<ParseError>) {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    let mut i = 0;
+
+    let arrow_at = |i: usize| chars[i] == '-' && chars.get(i + 1) == Some(&'>');
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
 
-    // Is it a number?
-    let maybe_number = term.parse::<u16>();
+        if c == '#' {
+            break;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if arrow_at(i) {
+            tokens.push((column, Token::Arrow));
+            i += 2;
+        } else {
+            let start = i;
+            while i < chars.len() && !chars[i].is_whitespace() && chars[i] != '#' && !arrow_at(i) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let word_char = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
 
-    match maybe_number {
-        Ok(number) => return Term::Literal(number),
-        Err(_) => return Term::Variable(term.clone()),
+            let error = |offset: usize, kind| ParseError {
+                line,
+                column: column + offset,
+                kind,
+            };
+            let token = if let Some(bad) = word.chars().position(|c| !word_char(&c)) {
+                errors.push(error(bad, ErrorKind::BadCharacter(chars[start + bad])));
+                Token::Invalid
+            } else if c.is_ascii_digit() {
+                match word.chars().position(|c| !c.is_ascii_digit()) {
+                    Some(letter) => {
+                        errors.push(error(letter, ErrorKind::BadNumber(word)));
+                        Token::Invalid
+                    }
+                    None => Token::Number(word),
+                }
+            } else {
+                match word.as_str() {
+                    "AND" => Token::Binary(BinaryOp::And),
+                    "OR" => Token::Binary(BinaryOp::Or),
+                    "NOT" => Token::Unary(UnaryOp::Not),
+                    "LSHIFT" => Token::Binary(BinaryOp::LShift),
+                    "RSHIFT" => Token::Binary(BinaryOp::RShift),
+                    _ => Token::Ident(word),
+                }
+            };
+            tokens.push((column, token));
+        }
     }
+
+    (tokens, errors)
 }
 
-fn reduce_lhs<'a>(s: &'a str) -> Exp {
-    let term = s.to_string();
+// A cursor over the tokens of a single line.
+struct Parser {
+    line: usize,
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
 
-    // Is it a number?
-    let maybe_number = term.parse::<u16>();
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
 
-    match maybe_number {
-        Ok(number) => return Exp::Literal(number),
-        Err(_) => return Exp::Variable(term.clone()),
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|(c, _)| *c)
+            .unwrap_or(self.end)
+    }
+
+    fn error(&self, expected: &'static str) -> ParseError {
+        let found = match self.peek() {
+            Some(t) => t.to_string(),
+            None => "end of line".to_owned(),
+        };
+        ParseError {
+            line: self.line,
+            column: self.column(),
+            kind: ErrorKind::Unexpected { found, expected },
+        }
+    }
+
+    /// Given a token representing a term in an expression return its
+    /// type, either a Variable (like "x") or a Literal (like 1).
+    fn term<W: Word>(&mut self) -> Result<Term<W>, ParseError> {
+        let column = self.column();
+        let term = match self.peek() {
+            Some(Token::Ident(i)) => Term::Variable(i.to_owned()),
+            Some(Token::Number(n)) => match n.parse::<W>() {
+                Ok(number) => Term::Literal(number),
+                Err(_) => {
+                    return Err(ParseError {
+                        line: self.line,
+                        column,
+                        kind: ErrorKind::Overflow {
+                            literal: n.to_owned(),
+                            bits: W::BITS,
+                        },
+                    })
+                }
+            },
+            _ => return Err(self.error("a wire or a number")),
+        };
+        self.pos += 1;
+        Ok(term)
+    }
+
+    fn exp<W: Word>(&mut self) -> Result<Exp<W>, ParseError> {
+        if let Some(Token::Unary(op)) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            return Ok(Exp::UnaryExp(op, self.term()?));
+        }
+
+        let t1 = self.term()?;
+        match self.peek() {
+            Some(Token::Binary(op)) => {
+                let op = *op;
+                self.pos += 1;
+                Ok(Exp::BinaryExp(op, t1, self.term()?))
+            }
+            Some(Token::Arrow) => Ok(match t1 {
+                Term::Literal(l) => Exp::Literal(l),
+                Term::Variable(v) => Exp::Variable(v),
+            }),
+            _ => Err(self.error("an operator or `->`")),
+        }
+    }
+
+    // Record that something else was expected at the current token,
+    // unless the token was already reported as invalid.
+    fn expect(&self, expected: &'static str, errors: &mut Vec<ParseError>) {
+        if self.peek() != Some(&Token::Invalid) {
+            errors.push(self.error(expected));
+        }
+    }
+
+    // Skip ahead to the next `->`, or the end of the line.
+    fn skip_to_arrow(&mut self) {
+        while self.peek().is_some_and(|t| *t != Token::Arrow) {
+            self.pos += 1;
+        }
+    }
+
+    // Parse the assignment, recording every error found.  After an
+    // error in the expression, parsing resumes at the `->`.
+    fn assignment<W: Word>(&mut self, errors: &mut Vec<ParseError>) -> Option<Assignment<W>> {
+        let exp = match self.exp() {
+            Ok(exp) => {
+                if self.peek() != Some(&Token::Arrow) {
+                    self.expect("`->`", errors);
+                }
+                Some(exp)
+            }
+            Err(e) => {
+                // The error is at the current token.
+                if self.peek() != Some(&Token::Invalid) {
+                    errors.push(e);
+                }
+                None
+            }
+        };
+        self.skip_to_arrow();
+        self.peek()?;
+        self.pos += 1;
+
+        let id = match self.peek() {
+            Some(Token::Ident(i)) => Some(i.to_owned()),
+            _ => {
+                self.expect("a wire", errors);
+                None
+            }
+        };
+        if self.peek().is_some() {
+            self.pos += 1;
+        }
+        if self.peek().is_some() {
+            self.expect("end of line", errors);
+        }
+
+        match (exp, id) {
+            (Some(exp), Some(id)) => Some(Assignment { id, exp, val: None }),
+            _ => None,
+        }
+    }
+}
+
+// Parse the line, returning its assignment if it holds one and has no
+// errors, and every error found on it.  A blank line, or one that is
+// only a comment, holds no assignment.
+fn parse_line<W: Word>(line: usize, s: &str) -> (Option<Assignment<W>>, Vec<ParseError>) {
+    let (tokens, mut errors) = tokenize(line, s);
+    if tokens.is_empty() {
+        return (None, errors);
+    }
+    let mut parser = Parser {
+        line,
+        tokens,
+        pos: 0,
+        end: s.chars().count() + 1,
+    };
+    let assign = parser.assignment(&mut errors);
+    if errors.is_empty() {
+        (assign, errors)
+    } else {
+        errors.sort_by_key(|e| e.column);
+        (None, errors)
     }
 }
 
 /// Given a string representing an assignment return its parsed
-/// Assignment structure.
-pub fn parse<'a>(s: &'a str) -> Result<Box<Assignment>, ()> {
-    let exp;
-    let cap;
-
-    // Parse any assignment containing a binary expression.
-    if s.contains("RSHIFT") || s.contains("LSHIFT") || s.contains("AND") || s.contains("OR") {
-        let re = Regex::new(
-            r"^(?P<exp1>\w{1,2}) (?P<op>RSHIFT|LSHIFT|AND|OR) (?P<exp2>\w{1,2}) -> (?P<id>\w{1,2})$",
-        )
-        .unwrap();
-        cap = re.captures(&s).unwrap();
-
-        let f = match cap.name("op").unwrap().as_str() {
-            "RSHIFT" => aoc_rshift,
-            "LSHIFT" => aoc_lshift,
-            "OR" => aoc_or,
-            "AND" => aoc_and,
-            _ => panic!("You can't always get what you want."),
-        };
+/// Assignment structure.  The grammar is:
+///
+///    <assignment> ::= <exp> -> <id>
+///    <exp>        ::= <term> | NOT <term> | <term> <op> <term>
+///    <op>         ::= AND | OR | LSHIFT | RSHIFT
+///    <term>       ::= <id> | <number>
+/// If the line has errors, the first of them is returned.
+pub fn parse<W: Word>(s: &str) -> Result<Box<Assignment<W>>, ParseError> {
+    let (assign, errors) = parse_line(1, s);
+    if let Some(e) = errors.into_iter().next() {
+        return Err(e);
+    }
+    match assign {
+        Some(assign) => Ok(Box::new(assign)),
+        None => Err(ParseError {
+            line: 1,
+            column: s.chars().count() + 1,
+            kind: ErrorKind::Unexpected {
+                found: "end of line".to_owned(),
+                expected: "an assignment",
+            },
+        }),
+    }
+}
 
-        exp = Exp::BinaryExp(
-            f,
-            reduce(cap.name("exp1").unwrap().as_str()),
-            reduce(cap.name("exp2").unwrap().as_str()),
-        );
+/// Given the text of a circuit, one assignment per line, return every
+/// assignment in it, skipping blank lines and comments.  If any line is
+/// not an assignment, return every error found instead, in order.  An
+/// error in a line's expression does not hide one in the wire it
+/// assigns, nor does an invalid word hide errors in the words after it.
+pub fn parse_program<W: Word>(s: &str) -> Result<Vec<Assignment<W>>, Vec<ParseError>> {
+    let mut assignments = Vec::new();
+    let mut errors = Vec::new();
+
+    for (n, line) in s.lines().enumerate() {
+        let (assign, line_errors) = parse_line(n + 1, line);
+        assignments.extend(assign);
+        errors.extend(line_errors);
     }
-    // Parse the assignment `NOT <id> -> <id>`
-    else if s.contains("NOT") {
-        let re = Regex::new(r"^NOT (?P<exp>\w{1,2}) -> (?P<id>\w{1,2})$").unwrap();
-        cap = re.captures(&s).unwrap();
-        exp = Exp::UnaryExp(aoc_not, reduce(cap.name("exp").unwrap().as_str()));
-    // Parse the assignment `u16 -> <id>` or `<id> -> <id>`
+
+    if errors.is_empty() {
+        Ok(assignments)
     } else {
-        let re = Regex::new(r"^(?P<lhs>\w{1,5}) -> (?P<id>\w{1,2})$").unwrap();
-        cap = re.captures(&s).unwrap();
-        exp = reduce_lhs(cap.name("lhs").unwrap().as_str());
-    }
-    let assign = Assignment {
-        id: cap.name("id").unwrap().as_str().to_string(),
-        exp,
-        val: None,
-    };
-    return Ok(Box::new(assign));
+        Err(errors)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{aoc_and, aoc_not, aoc_or, parse};
-    use crate::{Assignment, Exp, Term};
+    use super::{parse_program, ErrorKind, ParseError};
+    use crate::types::{Assignment, BinaryOp, Exp, Term, UnaryOp};
     use std::cmp::Ordering;
 
+    // The puzzle's circuits are 16 bits wide.
+    fn parse(s: &str) -> Result<Box<Assignment>, ParseError> {
+        super::parse(s)
+    }
+
     #[test]
     fn compare_expressions() {
         let mut a1;
@@ -153,7 +399,7 @@ mod tests {
             Box::new(Assignment {
                 id: "d".to_owned(),
                 exp: Exp::BinaryExp(
-                    aoc_and,
+                    BinaryOp::And,
                     Term::Variable("x".to_owned()),
                     Term::Variable("y".to_owned())
                 ),
@@ -164,7 +410,11 @@ mod tests {
             parse("1 AND y -> d").unwrap(),
             Box::new(Assignment {
                 id: "d".to_owned(),
-                exp: Exp::BinaryExp(aoc_and, Term::Literal(1), Term::Variable("y".to_owned())),
+                exp: Exp::BinaryExp(
+                    BinaryOp::And,
+                    Term::Literal(1),
+                    Term::Variable("y".to_owned())
+                ),
                 val: None
             })
         );
@@ -173,7 +423,11 @@ mod tests {
             parse("x AND 0 -> d").unwrap(),
             Box::new(Assignment {
                 id: "d".to_owned(),
-                exp: Exp::BinaryExp(aoc_and, Term::Variable("x".to_owned()), Term::Literal(0)),
+                exp: Exp::BinaryExp(
+                    BinaryOp::And,
+                    Term::Variable("x".to_owned()),
+                    Term::Literal(0)
+                ),
                 val: None
             })
         );
@@ -186,7 +440,7 @@ mod tests {
             Box::new(Assignment {
                 id: "e".to_owned(),
                 exp: Exp::BinaryExp(
-                    aoc_or,
+                    BinaryOp::Or,
                     Term::Variable("x".to_owned()),
                     Term::Variable("y".to_owned())
                 ),
@@ -197,7 +451,11 @@ mod tests {
             parse("0 OR y -> e").unwrap(),
             Box::new(Assignment {
                 id: "e".to_owned(),
-                exp: Exp::BinaryExp(aoc_or, Term::Literal(0), Term::Variable("y".to_owned())),
+                exp: Exp::BinaryExp(
+                    BinaryOp::Or,
+                    Term::Literal(0),
+                    Term::Variable("y".to_owned())
+                ),
                 val: None
             })
         );
@@ -205,7 +463,11 @@ mod tests {
             parse("y OR 0 -> e").unwrap(),
             Box::new(Assignment {
                 id: "e".to_owned(),
-                exp: Exp::BinaryExp(aoc_or, Term::Variable("y".to_owned()), Term::Literal(0)),
+                exp: Exp::BinaryExp(
+                    BinaryOp::Or,
+                    Term::Variable("y".to_owned()),
+                    Term::Literal(0)
+                ),
                 val: None
             }),
         );
@@ -217,7 +479,7 @@ mod tests {
             parse("NOT y -> e").unwrap(),
             Box::new(Assignment {
                 id: "e".to_owned(),
-                exp: Exp::UnaryExp(aoc_not, Term::Variable("y".to_owned())),
+                exp: Exp::UnaryExp(UnaryOp::Not, Term::Variable("y".to_owned())),
                 val: None
             })
         );
@@ -225,9 +487,214 @@ mod tests {
             parse("NOT 1 -> e").unwrap(),
             Box::new(Assignment {
                 id: "e".to_owned(),
-                exp: Exp::UnaryExp(aoc_not, Term::Literal(1)),
+                exp: Exp::UnaryExp(UnaryOp::Not, Term::Literal(1)),
+                val: None
+            })
+        );
+    }
+
+    #[test]
+    fn parse_literal_overflow() {
+        let overflow = |column, literal: &str, bits| ParseError {
+            line: 1,
+            column,
+            kind: ErrorKind::Overflow {
+                literal: literal.to_owned(),
+                bits,
+            },
+        };
+
+        assert_eq!(parse("70000 -> x").unwrap_err(), overflow(1, "70000", 16));
+        assert_eq!(
+            parse("x AND 65536 -> y").unwrap_err(),
+            overflow(7, "65536", 16)
+        );
+        assert_eq!(
+            super::parse::<u8>("NOT 256 -> y").unwrap_err(),
+            overflow(5, "256", 8)
+        );
+        assert_eq!(
+            super::parse::<u32>("70000 -> x").unwrap(),
+            Box::new(Assignment {
+                id: "x".to_owned(),
+                exp: Exp::Literal(70000),
                 val: None
             })
         );
     }
+
+    #[test]
+    fn parse_long_identifiers() {
+        assert_eq!(
+            parse("carry_in OR orange -> sum2").unwrap(),
+            Box::new(Assignment {
+                id: "sum2".to_owned(),
+                exp: Exp::BinaryExp(
+                    BinaryOp::Or,
+                    Term::Variable("carry_in".to_owned()),
+                    Term::Variable("orange".to_owned())
+                ),
+                val: None
+            })
+        );
+        // Only the upper case operator names are reserved.
+        assert_eq!(
+            parse("NOT or -> and").unwrap(),
+            Box::new(Assignment {
+                id: "and".to_owned(),
+                exp: Exp::UnaryExp(UnaryOp::Not, Term::Variable("or".to_owned())),
+                val: None
+            })
+        );
+        assert_eq!(
+            parse("  x   LSHIFT 2->y  # shift it").unwrap(),
+            Box::new(Assignment {
+                id: "y".to_owned(),
+                exp: Exp::BinaryExp(
+                    BinaryOp::LShift,
+                    Term::Variable("x".to_owned()),
+                    Term::Literal(2)
+                ),
+                val: None
+            })
+        );
+    }
+
+    #[test]
+    fn report_syntax_errors() {
+        let unexpected = |column, found: &str, expected| ParseError {
+            line: 1,
+            column,
+            kind: ErrorKind::Unexpected {
+                found: found.to_owned(),
+                expected,
+            },
+        };
+
+        assert_eq!(
+            parse("x AND -> y").unwrap_err(),
+            unexpected(7, "`->`", "a wire or a number")
+        );
+        assert_eq!(
+            parse("x y -> z").unwrap_err(),
+            unexpected(3, "`y`", "an operator or `->`")
+        );
+        assert_eq!(
+            parse("x AND y z").unwrap_err(),
+            unexpected(9, "`z`", "`->`")
+        );
+        assert_eq!(parse("x -> 3").unwrap_err(), unexpected(6, "`3`", "a wire"));
+        assert_eq!(
+            parse("x -> y z").unwrap_err(),
+            unexpected(8, "`z`", "end of line")
+        );
+        assert_eq!(
+            parse("x NOT y -> z").unwrap_err(),
+            unexpected(3, "`NOT`", "an operator or `->`")
+        );
+        assert_eq!(
+            parse("x AND y ->").unwrap_err(),
+            unexpected(11, "end of line", "a wire")
+        );
+        assert_eq!(
+            parse("# nothing").unwrap_err(),
+            unexpected(10, "end of line", "an assignment")
+        );
+        assert_eq!(
+            parse("x & y -> z").unwrap_err(),
+            ParseError {
+                line: 1,
+                column: 3,
+                kind: ErrorKind::BadCharacter('&')
+            }
+        );
+        assert_eq!(
+            parse("x AND -> y").unwrap_err().to_string(),
+            "line 1, column 7: expected a wire or a number, found `->`"
+        );
+    }
+
+    #[test]
+    fn parse_programs() {
+        let program = "# A half adder\n\
+                       \n\
+                       x AND y -> carry\n\
+                       x OR y -> either   # or both\n\
+                       \n\
+                       1 -> x\n\
+                       0 -> y\n";
+        let assignments: Vec<Assignment> = parse_program(program).unwrap();
+        let wires: Vec<&str> = assignments.iter().map(|a| a.id.as_str()).collect();
+        assert_eq!(wires, vec!["carry", "either", "x", "y"]);
+
+        let program = "x AND -> carry\n\
+                       1 -> x\n\
+                       NOT NOT x -> y\n\
+                       # fine\n\
+                       x -> $z\n";
+        let errors = parse_program::<u16>(program).unwrap_err();
+        let places: Vec<(usize, usize)> = errors.iter().map(|e| (e.line, e.column)).collect();
+        assert_eq!(places, vec![(1, 7), (3, 5), (5, 6)]);
+    }
+
+    #[test]
+    fn report_every_error_on_a_line() {
+        let places = |program: &str| -> Vec<(usize, usize)> {
+            parse_program::<u16>(program)
+                .unwrap_err()
+                .iter()
+                .map(|e| (e.line, e.column))
+                .collect()
+        };
+
+        // An error in the expression, then in the wire assigned.
+        assert_eq!(places("x AND -> 3"), vec![(1, 7), (1, 10)]);
+        // Bad characters, and what follows them.
+        assert_eq!(places("x & y -> $z w"), vec![(1, 3), (1, 10), (1, 13)]);
+        assert_eq!(
+            places("x AND y z -> 4\nNOT -> q"),
+            vec![(1, 9), (1, 14), (2, 5)]
+        );
+        // An invalid word is reported once, not again by the parser.
+        assert_eq!(places("x-y -> z"), vec![(1, 2)]);
+    }
+
+    #[test]
+    fn identifiers_and_numbers() {
+        let error = |column, kind| ParseError {
+            line: 1,
+            column,
+            kind,
+        };
+
+        assert_eq!(
+            parse("_x AND y_2 -> z").unwrap().exp,
+            Exp::BinaryExp(
+                BinaryOp::And,
+                Term::Variable("_x".to_owned()),
+                Term::Variable("y_2".to_owned())
+            )
+        );
+        assert_eq!(
+            parse("1abc -> x").unwrap_err(),
+            error(2, ErrorKind::BadNumber("1abc".to_owned()))
+        );
+        assert_eq!(
+            parse("x -> 12_b").unwrap_err(),
+            error(8, ErrorKind::BadNumber("12_b".to_owned()))
+        );
+        assert_eq!(
+            parse("x -> 1abc").unwrap_err().to_string(),
+            "line 1, column 7: `1abc` is not a number"
+        );
+        // Only ASCII letters make identifiers.
+        assert_eq!(
+            parse("NOT é -> x").unwrap_err(),
+            error(5, ErrorKind::BadCharacter('é'))
+        );
+        assert_eq!(
+            parse("x OR a١ -> y").unwrap_err(),
+            error(7, ErrorKind::BadCharacter('١'))
+        );
+    }
 }
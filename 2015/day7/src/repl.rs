@@ -0,0 +1,356 @@
+use crate::circuit::CircuitError;
+use crate::eval_expr;
+use crate::parse::{parse, parse_program};
+use crate::types::{Assignment, State, Word};
+
+use anyhow::{anyhow, Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+const HELP: &str = "\
+<exp> -> <wire>   add or replace the assignment driving a wire
+load <file>       add every assignment in a file
+? <wire>          show a wire's value
+trace <wire>      show the gates that produced a wire's value
+reset             forget every assignment
+help              show this message
+quit              leave";
+
+/// An interactive session editing a circuit one assignment at a time.
+/// Unlike a Circuit, a session may read wires that are not yet driven;
+/// their values, and the values of every wire downstream of them, are
+/// simply unknown until they are.
+#[derive(Debug)]
+pub struct Session<W = u16> {
+    assignments: HashMap<String, Assignment<W>>,
+    readers: HashMap<String, HashSet<String>>,
+    state: State<W>,
+}
+
+impl<W: Word> Session<W> {
+    pub fn new() -> Self {
+        Session {
+            assignments: HashMap::new(),
+            readers: HashMap::new(),
+            state: State::new(),
+        }
+    }
+
+    /// The wire's current value, if it can be known.
+    pub fn value(&self, wire: &str) -> Option<W> {
+        self.state.known.get(wire).copied()
+    }
+
+    // Search upstream of `wire` for `target`, returning the wires along
+    // the way if it is found.
+    fn path_to(&self, wire: &str, target: &str, seen: &mut HashSet<String>) -> Option<Vec<String>> {
+        if wire == target {
+            return Some(vec![wire.to_owned()]);
+        }
+        if !seen.insert(wire.to_owned()) {
+            return None;
+        }
+        let a = self.assignments.get(wire)?;
+        for input in a.exp.inputs() {
+            if let Some(mut path) = self.path_to(input, target, seen) {
+                path.push(wire.to_owned());
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    /// Add the assignment, replacing any assignment already driving its
+    /// wire, and recompute the wires downstream of it.  Returns how many
+    /// wires were recomputed.
+    pub fn set(&mut self, assign: Assignment<W>) -> Result<usize, CircuitError> {
+        let mut seen = HashSet::new();
+        for input in assign.exp.inputs() {
+            if let Some(path) = self.path_to(input, &assign.id, &mut seen) {
+                return Err(CircuitError::Cycle(path));
+            }
+        }
+
+        let id = assign.id.to_owned();
+        if let Some(old) = self.assignments.remove(&id) {
+            for input in old.exp.inputs() {
+                if let Some(r) = self.readers.get_mut(input) {
+                    r.remove(&id);
+                }
+            }
+        }
+        for input in assign.exp.inputs() {
+            self.readers
+                .entry(input.to_owned())
+                .or_default()
+                .insert(id.to_owned());
+        }
+        self.assignments.insert(id.to_owned(), assign);
+
+        Ok(self.recompute(&id))
+    }
+
+    // Forget the value of the wire and every wire downstream of it, then
+    // evaluate those wires again in dependency order.
+    fn recompute(&mut self, wire: &str) -> usize {
+        let mut stale = HashSet::new();
+        let mut pending = vec![wire.to_owned()];
+        while let Some(w) = pending.pop() {
+            if stale.contains(&w) {
+                continue;
+            }
+            if let Some(r) = self.readers.get(&w) {
+                pending.extend(r.iter().cloned());
+            }
+            stale.insert(w);
+        }
+        for w in stale.iter() {
+            self.state.known.remove(w);
+        }
+
+        // Evaluate a stale wire once none of its inputs are stale.
+        let mut waiting: HashMap<&str, usize> = HashMap::new();
+        for w in stale.iter() {
+            if let Some(a) = self.assignments.get(w) {
+                let count = a
+                    .exp
+                    .inputs()
+                    .iter()
+                    .filter(|i| stale.contains(**i))
+                    .count();
+                waiting.insert(w.as_str(), count);
+            }
+        }
+        let mut ready: Vec<&str> = waiting
+            .iter()
+            .filter(|(_, count)| **count == 0)
+            .map(|(w, _)| *w)
+            .collect();
+
+        let mut recomputed = 0;
+        while let Some(w) = ready.pop() {
+            if let Some(v) = eval_expr(&self.assignments[w].exp, &self.state.known) {
+                self.state.known.insert(w.to_owned(), v);
+                recomputed += 1;
+            }
+            for r in self.readers.get(w).into_iter().flatten() {
+                if let Some(count) = waiting.get_mut(r.as_str()) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push(r);
+                    }
+                }
+            }
+        }
+
+        recomputed
+    }
+
+    /// The assignments that produced the wire's value, in the order they
+    /// were evaluated, each followed by its value.
+    pub fn trace(&self, wire: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut seen = HashSet::new();
+        self.trace_from(wire, &mut seen, &mut lines);
+        lines
+    }
+
+    fn trace_from(&self, wire: &str, seen: &mut HashSet<String>, lines: &mut Vec<String>) {
+        if !seen.insert(wire.to_owned()) {
+            return;
+        }
+        match self.assignments.get(wire) {
+            Some(a) => {
+                for input in a.exp.inputs() {
+                    self.trace_from(input, seen, lines);
+                }
+                match self.value(wire) {
+                    Some(v) => lines.push(format!("{}    # {}", a, v)),
+                    None => lines.push(format!("{}    # unknown", a)),
+                }
+            }
+            None => lines.push(format!("# {} has no driver", wire)),
+        }
+    }
+
+    /// Carry out a single line of input, returning what to show for it.
+    pub fn command(&mut self, line: &str) -> Result<String> {
+        let line = line.trim();
+        let (word, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        // A comment is ignored, even one holding an arrow.  Anything
+        // else with an arrow is an assignment, even one driving a wire
+        // named like a command.
+        if line.starts_with('#') {
+            return Ok(String::new());
+        }
+        if line.contains("->") {
+            let a = parse(line)?;
+            let recomputed = self.set(*a)?;
+            return Ok(format!("recomputed {} wires", recomputed));
+        }
+
+        match word {
+            "" => Ok(String::new()),
+            "help" => Ok(HELP.to_owned()),
+            "reset" => {
+                *self = Session::new();
+                Ok("reset".to_owned())
+            }
+            "?" => match self.value(rest) {
+                Some(v) => Ok(format!("{} = {}", rest, v)),
+                None => Ok(format!("{} is unknown", rest)),
+            },
+            "trace" => Ok(self.trace(rest).join("\n")),
+            "load" => {
+                let s = fs::read_to_string(rest).with_context(|| format!("reading {}", rest))?;
+                let assignments = parse_program(&s).map_err(|errors| {
+                    let lines: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                    anyhow!("{}", lines.join("\n"))
+                })?;
+                let loaded = assignments.len();
+                for a in assignments {
+                    let id = a.id.to_owned();
+                    self.set(a).with_context(|| format!("assigning {}", id))?;
+                }
+                Ok(format!("loaded {} assignments", loaded))
+            }
+            _ => Err(anyhow!("unknown command `{}`, try `help`", word)),
+        }
+    }
+}
+
+/// Read commands from standard input until it ends or `quit`, after
+/// first loading the file if one is given.
+pub fn run<W: Word>(file: Option<&str>) -> Result<()> {
+    let mut session = Session::<W>::new();
+    if let Some(f) = file {
+        println!("{}", session.command(&format!("load {}", f))?);
+    }
+
+    let stdin = io::stdin();
+    print!("> ");
+    io::stdout().flush()?;
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim() == "quit" {
+            break;
+        }
+        match session.command(&line) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => println!("{}", out),
+            Err(e) => println!("error: {:#}", e),
+        }
+        print!("> ");
+        io::stdout().flush()?;
+    }
+    println!();
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+
+    #[test]
+    fn query_and_edit() {
+        let mut s: Session = Session::new();
+        assert_eq!(s.command("x AND y -> d").unwrap(), "recomputed 0 wires");
+        assert_eq!(s.command("? d").unwrap(), "d is unknown");
+
+        assert_eq!(s.command("123 -> x").unwrap(), "recomputed 1 wires");
+        assert_eq!(s.command("456 -> y").unwrap(), "recomputed 2 wires");
+        assert_eq!(s.command("? d").unwrap(), "d = 72");
+
+        s.command("NOT d -> e").unwrap();
+        s.command("y RSHIFT 2 -> g").unwrap();
+        assert_eq!(s.command("? e").unwrap(), "e = 65463");
+
+        // Only x and what lies downstream of it are recomputed.
+        assert_eq!(s.command("7 -> x").unwrap(), "recomputed 3 wires");
+        assert_eq!(s.command("? d").unwrap(), "d = 0");
+        assert_eq!(s.command("? e").unwrap(), "e = 65535");
+        assert_eq!(s.command("? g").unwrap(), "g = 114");
+
+        assert_eq!(s.command("reset").unwrap(), "reset");
+        assert_eq!(s.command("? g").unwrap(), "g is unknown");
+    }
+
+    #[test]
+    fn comments() {
+        let mut s: Session = Session::new();
+        assert_eq!(s.command("# a -> b").unwrap(), "");
+        assert_eq!(s.command("  #nothing").unwrap(), "");
+        assert_eq!(s.command("? b").unwrap(), "b is unknown");
+    }
+
+    #[test]
+    fn gate_reading_one_wire_twice() {
+        let mut s: Session = Session::new();
+        s.command("1 -> x").unwrap();
+        s.command("x AND x -> y").unwrap();
+        assert_eq!(s.command("? y").unwrap(), "y = 1");
+        assert_eq!(s.command("3 -> x").unwrap(), "recomputed 2 wires");
+        assert_eq!(s.command("? y").unwrap(), "y = 3");
+    }
+
+    #[test]
+    fn trace() {
+        let mut s: Session = Session::new();
+        for l in ["x AND y -> d", "NOT d -> e", "123 -> x"].iter() {
+            s.command(l).unwrap();
+        }
+        assert_eq!(
+            s.command("trace e").unwrap(),
+            "123 -> x    # 123\n\
+             # y has no driver\n\
+             x AND y -> d    # unknown\n\
+             NOT d -> e    # unknown"
+        );
+    }
+
+    #[test]
+    fn reject_bad_input() {
+        let mut s: Session = Session::new();
+        s.command("1 -> a").unwrap();
+        s.command("a AND 1 -> b").unwrap();
+        s.command("NOT b -> c").unwrap();
+
+        let e = s.command("c -> a").unwrap_err();
+        assert_eq!(e.to_string(), "wires form a cycle: a -> b -> c -> a");
+        assert_eq!(s.command("? c").unwrap(), "c = 65534");
+
+        assert!(s.command("a AND -> b").is_err());
+        assert_eq!(
+            s.command("70000 -> b").unwrap_err().to_string(),
+            "line 1, column 1: literal 70000 does not fit in 16 bits"
+        );
+        assert!(s.command("load /no/such/file").is_err());
+        assert_eq!(
+            s.command("bogus").unwrap_err().to_string(),
+            "unknown command `bogus`, try `help`"
+        );
+
+        // Wires may share a name with a command, and comments are ignored.
+        assert_eq!(s.command("# a comment").unwrap(), "");
+        s.command("a LSHIFT 2 -> trace  # four").unwrap();
+        assert_eq!(s.command("? trace").unwrap(), "trace = 4");
+    }
+
+    #[test]
+    fn other_widths() {
+        let mut s: Session<u8> = Session::new();
+        s.command("255 -> x").unwrap();
+        s.command("x LSHIFT 4 -> y").unwrap();
+        assert_eq!(s.command("? y").unwrap(), "y = 240");
+        assert!(s.command("256 -> x").is_err());
+
+        let mut s: Session<u64> = Session::new();
+        s.command("4294967296 -> x").unwrap();
+        s.command("NOT x -> y").unwrap();
+        assert_eq!(s.command("? y").unwrap(), "y = 18446744069414584319");
+    }
+}
@@ -0,0 +1,261 @@
+use crate::circuit::{Circuit, CircuitError};
+use crate::types::{Assignment, BinaryOp, Exp, Term, UnaryOp, Word};
+
+use std::collections::{HashMap, HashSet};
+
+/// A simplified circuit and how much smaller it is than the original.
+#[derive(Debug)]
+pub struct Simplified<W = u16> {
+    pub circuit: Circuit<W>,
+    /// The number of gates, i.e. assignments with an operator, that no
+    /// longer appear in the simplified circuit.
+    pub eliminated: usize,
+}
+
+fn gates<'a, W: Word + 'a>(assignments: impl Iterator<Item = &'a Assignment<W>>) -> usize {
+    assignments
+        .filter(|a| matches!(a.exp, Exp::UnaryExp(..) | Exp::BinaryExp(..)))
+        .count()
+}
+
+// An expression that is just the term.
+fn term_exp<W>(t: Term<W>) -> Exp<W> {
+    match t {
+        Term::Literal(l) => Exp::Literal(l),
+        Term::Variable(v) => Exp::Variable(v),
+    }
+}
+
+// Replace a variable that has already simplified to a literal or to
+// another variable with what it simplified to.
+fn substitute<W: Word>(t: &Term<W>, simplified: &HashMap<String, Exp<W>>) -> Term<W> {
+    match t {
+        Term::Variable(v) => match &simplified[v] {
+            Exp::Literal(l) => Term::Literal(*l),
+            Exp::Variable(w) => Term::Variable(w.to_owned()),
+            _ => t.clone(),
+        },
+        Term::Literal(_) => t.clone(),
+    }
+}
+
+// Simplify a single expression whose terms have been substituted.
+fn simplify_exp<W: Word>(exp: Exp<W>, simplified: &HashMap<String, Exp<W>>) -> Exp<W> {
+    match exp {
+        Exp::UnaryExp(op, Term::Literal(l)) => Exp::Literal(op.apply(l)),
+        Exp::UnaryExp(UnaryOp::Not, Term::Variable(v)) => match &simplified[&v] {
+            // NOT NOT x is x.
+            Exp::UnaryExp(UnaryOp::Not, t) => term_exp(t.clone()),
+            _ => Exp::UnaryExp(UnaryOp::Not, Term::Variable(v)),
+        },
+        Exp::BinaryExp(op, Term::Literal(a), Term::Literal(b)) => Exp::Literal(op.apply(a, b)),
+        Exp::BinaryExp(op, a, b) => {
+            let zero = Term::Literal(W::ZERO);
+            let ones = Term::Literal(W::MAX);
+            let too_far = matches!(b, Term::Literal(s) if s.as_shift() >= W::BITS);
+            match op {
+                BinaryOp::And if a == zero || b == zero => Exp::Literal(W::ZERO),
+                BinaryOp::And if a == ones => term_exp(b),
+                BinaryOp::And if b == ones => term_exp(a),
+                BinaryOp::Or if a == ones || b == ones => Exp::Literal(W::MAX),
+                BinaryOp::Or if a == zero => term_exp(b),
+                BinaryOp::Or if b == zero => term_exp(a),
+                BinaryOp::LShift | BinaryOp::RShift if b == zero => term_exp(a),
+                BinaryOp::LShift | BinaryOp::RShift if a == zero || too_far => {
+                    Exp::Literal(W::ZERO)
+                }
+                _ => Exp::BinaryExp(op, a, b),
+            }
+        }
+        _ => exp,
+    }
+}
+
+/// Simplify the circuit by folding constant subexpressions, applying
+/// the identities `x AND 0xFFFF` (or whatever is all ones in the
+/// circuit's word), `x OR 0`, `x LSHIFT 0` and `NOT NOT x` (among
+/// others), and removing every gate that none of the
+/// `outputs` depend upon.  With no outputs, no gates are removed for
+/// being unreachable.
+///
+/// Every wire in a circuit is ultimately driven by constants, so left
+/// alone folding reduces it entirely to literals.  The wires listed in
+/// `inputs` are instead treated as unknown, so that they may still be
+/// overridden in the simplified circuit.  Every wire kept has the value
+/// it would have had in the original circuit.
+pub fn simplify<W: Word>(
+    circuit: &Circuit<W>,
+    inputs: &[&str],
+    outputs: &[&str],
+) -> Result<Simplified<W>, CircuitError> {
+    let original = circuit.assignments();
+    for input in inputs.iter() {
+        if !original.iter().any(|a| a.id == *input) {
+            return Err(CircuitError::UnknownWire(input.to_string()));
+        }
+    }
+
+    // The assignments are in evaluation order, so every wire an
+    // expression reads has been simplified before the expression.
+    let mut simplified: HashMap<String, Exp<W>> = HashMap::new();
+    let mut assignments = Vec::new();
+    for a in original.iter() {
+        let exp = match &a.exp {
+            Exp::Variable(v) => term_exp(substitute(&Term::Variable(v.to_owned()), &simplified)),
+            Exp::UnaryExp(op, t) => Exp::UnaryExp(*op, substitute(t, &simplified)),
+            Exp::BinaryExp(op, t1, t2) => Exp::BinaryExp(
+                *op,
+                substitute(t1, &simplified),
+                substitute(t2, &simplified),
+            ),
+            Exp::Literal(_) => a.exp.clone(),
+        };
+        let exp = simplify_exp(exp, &simplified);
+
+        // An input stands for itself to the wires that read it.
+        if inputs.contains(&a.id.as_str()) {
+            simplified.insert(a.id.to_owned(), Exp::Variable(a.id.to_owned()));
+        } else {
+            simplified.insert(a.id.to_owned(), exp.clone());
+        }
+        assignments.push(Assignment {
+            id: a.id.to_owned(),
+            exp,
+            val: None,
+        });
+    }
+
+    let mut reduced = Circuit::new(assignments)?;
+
+    if !outputs.is_empty() {
+        let mut live = HashSet::new();
+        for output in outputs.iter() {
+            for a in reduced.fan_in(output)? {
+                live.insert(a.id.to_owned());
+            }
+        }
+        let assignments = reduced
+            .assignments()
+            .into_iter()
+            .filter(|a| live.contains(&a.id))
+            .cloned()
+            .collect();
+        reduced = Circuit::new(assignments)?;
+    }
+
+    let eliminated = gates(original.into_iter()) - gates(reduced.assignments().into_iter());
+
+    Ok(Simplified {
+        circuit: reduced,
+        eliminated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::simplify;
+    use crate::circuit::Circuit;
+    use crate::parse::parse;
+    use crate::types::{Exp, Term, UnaryOp};
+
+    fn circuit(lines: &[&str]) -> Circuit {
+        Circuit::new(lines.iter().map(|l| *parse(l).unwrap()).collect()).unwrap()
+    }
+
+    fn exp_of(c: &Circuit, wire: &str) -> Exp {
+        c.assignments()
+            .into_iter()
+            .find(|a| a.id == wire)
+            .unwrap()
+            .exp
+            .clone()
+    }
+
+    #[test]
+    fn fold_constants() {
+        let c = circuit(&["3 -> x", "x LSHIFT 2 -> y", "NOT y -> z", "y OR z -> a"]);
+        let s = simplify(&c, &[], &[]).unwrap();
+
+        assert_eq!(exp_of(&s.circuit, "y"), Exp::Literal(12));
+        assert_eq!(exp_of(&s.circuit, "z"), Exp::Literal(65523));
+        assert_eq!(exp_of(&s.circuit, "a"), Exp::Literal(65535));
+        assert_eq!(s.eliminated, 3);
+    }
+
+    #[test]
+    fn apply_identities() {
+        let c = circuit(&[
+            "123 -> p",
+            "NOT 0 -> m",
+            "p AND m -> b",
+            "0 OR b -> c",
+            "c LSHIFT 0 -> d",
+            "NOT d -> e",
+            "NOT e -> f",
+            "d AND 0 -> g",
+            "d RSHIFT 16 -> h",
+        ]);
+        let s = simplify(&c, &["p"], &[]).unwrap();
+
+        assert_eq!(exp_of(&s.circuit, "p"), Exp::Literal(123));
+        assert_eq!(exp_of(&s.circuit, "m"), Exp::Literal(65535));
+        assert_eq!(exp_of(&s.circuit, "b"), Exp::Variable("p".to_owned()));
+        assert_eq!(exp_of(&s.circuit, "c"), Exp::Variable("p".to_owned()));
+        assert_eq!(exp_of(&s.circuit, "d"), Exp::Variable("p".to_owned()));
+        assert_eq!(
+            exp_of(&s.circuit, "e"),
+            Exp::UnaryExp(UnaryOp::Not, Term::Variable("p".to_owned()))
+        );
+        assert_eq!(exp_of(&s.circuit, "f"), Exp::Variable("p".to_owned()));
+        assert_eq!(exp_of(&s.circuit, "g"), Exp::Literal(0));
+        assert_eq!(exp_of(&s.circuit, "h"), Exp::Literal(0));
+        assert_eq!(s.eliminated, 7);
+
+        // Only f and the input it reads reach the output.
+        let s = simplify(&c, &["p"], &["f"]).unwrap();
+        let wires: Vec<&str> = s
+            .circuit
+            .assignments()
+            .into_iter()
+            .map(|a| a.id.as_str())
+            .collect();
+        assert_eq!(wires, vec!["p", "f"]);
+        assert_eq!(s.eliminated, 8);
+
+        assert!(simplify(&c, &["z"], &[]).is_err());
+        assert!(simplify(&c, &[], &["z"]).is_err());
+    }
+
+    #[test]
+    fn simplified_outputs_match() {
+        let lines: Vec<&str> = include_str!("../input.txt").trim().split('\n').collect();
+        let mut original = circuit(&lines);
+        let s = simplify(&original, &["b"], &["a"]).unwrap();
+        let mut simplified = s.circuit;
+        assert!(s.eliminated > 0);
+
+        original.evaluate();
+        simplified.evaluate();
+        assert_eq!(original.value("a"), simplified.value("a"));
+
+        // The input is still free to be overridden.
+        for value in [0, 1, 3176, 16076, 65535].iter() {
+            original.override_wire("b", *value).unwrap();
+            simplified.override_wire("b", *value).unwrap();
+            original.evaluate();
+            simplified.evaluate();
+            assert_eq!(original.value("a"), simplified.value("a"));
+        }
+
+        // Without outputs or inputs, every wire folds to its value.
+        let mut original = circuit(&lines);
+        let s = simplify(&original, &[], &[]).unwrap();
+        let mut simplified = s.circuit;
+        original.evaluate();
+        simplified.evaluate();
+        for a in original.assignments() {
+            assert_eq!(a.val, simplified.value(&a.id));
+            assert_eq!(exp_of(&simplified, &a.id), Exp::Literal(a.val.unwrap()));
+        }
+    }
+}
@@ -1,14 +1,80 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::{BitAnd, BitOr, Not};
+use std::str::FromStr;
+
+/// The unsigned integer type carried by every wire of a circuit.  The
+/// puzzle's circuits are 16 bits wide, but any of u8, u16, u32 or u64
+/// may be used.
+pub trait Word:
+    Copy
+    + fmt::Debug
+    + fmt::Display
+    + Default
+    + Eq
+    + Ord
+    + Hash
+    + FromStr
+    + Not<Output = Self>
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+{
+    const BITS: u32;
+    const ZERO: Self;
+    const MAX: Self;
+
+    fn checked_shl(self, n: u32) -> Option<Self>;
+    fn checked_shr(self, n: u32) -> Option<Self>;
+
+    /// The word as a shift amount, saturating at u32::MAX.
+    fn as_shift(self) -> u32;
+
+    /// The low bits of the value, discarding those that do not fit.
+    fn truncate(v: u64) -> Self;
+}
+
+macro_rules! word {
+    ($t:ty) => {
+        impl Word for $t {
+            const BITS: u32 = <$t>::BITS;
+            const ZERO: Self = 0;
+            const MAX: Self = <$t>::MAX;
+
+            fn checked_shl(self, n: u32) -> Option<Self> {
+                <$t>::checked_shl(self, n)
+            }
+
+            fn checked_shr(self, n: u32) -> Option<Self> {
+                <$t>::checked_shr(self, n)
+            }
+
+            fn as_shift(self) -> u32 {
+                u32::try_from(self).unwrap_or(u32::MAX)
+            }
+
+            fn truncate(v: u64) -> Self {
+                v as $t
+            }
+        }
+    };
+}
+
+word!(u8);
+word!(u16);
+word!(u32);
+word!(u64);
 
 /// The program state is the set of variables whose values are known
 /// and the set of variables whose assigned expressions cannot yet be
 /// evaluated.
 #[derive(Debug)]
-pub struct State {
-    pub known: HashMap<String, u16>,
+pub struct State<W = u16> {
+    pub known: HashMap<String, W>,
 }
 
-impl State {
+impl<W> State<W> {
     pub fn new() -> Self {
         Self {
             known: HashMap::new(),
@@ -19,11 +85,88 @@ impl State {
 /// A term in an expression is one of a variable (like "x") or an
 /// unsigned integer (like 1).
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub enum Term {
-    Literal(u16),
+pub enum Term<W = u16> {
+    Literal(W),
     Variable(String),
 }
 
+impl<W: fmt::Display> fmt::Display for Term<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Literal(l) => write!(f, "{}", l),
+            Term::Variable(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+impl<W> Term<W> {
+    /// The name of the term if it is a variable.
+    pub fn variable(&self) -> Option<&str> {
+        match self {
+            Term::Literal(_) => None,
+            Term::Variable(v) => Some(v),
+        }
+    }
+}
+
+/// The bitwise operator taking a single operand.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub enum UnaryOp {
+    Not,
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOp::Not => write!(f, "NOT"),
+        }
+    }
+}
+
+impl UnaryOp {
+    /// Apply the operator to its operand.
+    pub fn apply<W: Word>(self, a: W) -> W {
+        match self {
+            UnaryOp::Not => !a,
+        }
+    }
+}
+
+/// A bitwise operator taking two operands, applied in the order they
+/// are written: `a AND b` is `a & b` and `a LSHIFT b` is `a` shifted
+/// left by `b` bits.  Shifting by the word's width or more yields 0.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub enum BinaryOp {
+    And,
+    Or,
+    LShift,
+    RShift,
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BinaryOp::And => "AND",
+            BinaryOp::Or => "OR",
+            BinaryOp::LShift => "LSHIFT",
+            BinaryOp::RShift => "RSHIFT",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl BinaryOp {
+    /// Apply the operator to its operands, `a <op> b`.
+    pub fn apply<W: Word>(self, a: W, b: W) -> W {
+        match self {
+            BinaryOp::And => a & b,
+            BinaryOp::Or => a | b,
+            BinaryOp::LShift => a.checked_shl(b.as_shift()).unwrap_or(W::ZERO),
+            BinaryOp::RShift => a.checked_shr(b.as_shift()).unwrap_or(W::ZERO),
+        }
+    }
+}
+
 /// An expression is any of:
 ///  123
 ///  1 AND y
@@ -32,20 +175,149 @@ pub enum Term {
 ///  x OR 0
 ///  NOT y
 ///  NOT 1
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub enum Exp {
-    Literal(u16),
+pub enum Exp<W = u16> {
+    Literal(W),
     Variable(String),
-    UnaryExp(fn(a: u16) -> u16, Term),
-    BinaryExp(fn(a: u16, b: u16) -> u16, Term, Term),
+    UnaryExp(UnaryOp, Term<W>),
+    BinaryExp(BinaryOp, Term<W>, Term<W>),
+}
+
+impl<W: fmt::Display> fmt::Display for Exp<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exp::Literal(l) => write!(f, "{}", l),
+            Exp::Variable(v) => write!(f, "{}", v),
+            Exp::UnaryExp(op, t) => write!(f, "{} {}", op, t),
+            Exp::BinaryExp(op, t1, t2) => write!(f, "{} {} {}", t1, op, t2),
+        }
+    }
+}
+
+impl<W> Exp<W> {
+    /// The names of the variables the expression reads, each named
+    /// once even if read twice, as in `x AND x`.
+    pub fn inputs(&self) -> Vec<&str> {
+        match self {
+            Exp::Literal(_) => vec![],
+            Exp::Variable(v) => vec![v],
+            Exp::UnaryExp(_, t) => t.variable().into_iter().collect(),
+            Exp::BinaryExp(_, t1, t2) => {
+                let mut inputs: Vec<&str> =
+                    t1.variable().into_iter().chain(t2.variable()).collect();
+                inputs.dedup();
+                inputs
+            }
+        }
+    }
 }
 
 /// An Assignment is an identifier and an expression.
 ///    i.e., <exp> -> <id>
 ///
-#[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
-pub struct Assignment {
-    pub exp: Exp,
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord)]
+pub struct Assignment<W = u16> {
+    pub exp: Exp<W>,
     pub id: String,
-    pub val: Option<u16>,
+    pub val: Option<W>,
+}
+
+impl<W: fmt::Display> fmt::Display for Assignment<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}", self.exp, self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Assignment, BinaryOp, UnaryOp};
+    use crate::parse::parse;
+
+    #[test]
+    fn and() {
+        assert_eq!(BinaryOp::And.apply::<u16>(0, 0), 0);
+        assert_eq!(BinaryOp::And.apply::<u16>(0xFFFF, 0), 0);
+        assert_eq!(BinaryOp::And.apply::<u16>(0xFFFF, 0x1234), 0x1234);
+        assert_eq!(BinaryOp::And.apply::<u16>(0xF0F0, 0xFF00), 0xF000);
+    }
+
+    #[test]
+    fn or() {
+        assert_eq!(BinaryOp::Or.apply::<u16>(0, 0), 0);
+        assert_eq!(BinaryOp::Or.apply::<u16>(0x1234, 0), 0x1234);
+        assert_eq!(BinaryOp::Or.apply::<u16>(0xF0F0, 0x0F0F), 0xFFFF);
+        assert_eq!(BinaryOp::Or.apply::<u16>(0xF0F0, 0xFF00), 0xFFF0);
+    }
+
+    #[test]
+    fn not() {
+        assert_eq!(UnaryOp::Not.apply::<u16>(0), 0xFFFF);
+        assert_eq!(UnaryOp::Not.apply::<u16>(0xFFFF), 0);
+        assert_eq!(UnaryOp::Not.apply::<u16>(123), 65412);
+    }
+
+    #[test]
+    fn lshift() {
+        // The first operand is shifted by the second.
+        assert_eq!(BinaryOp::LShift.apply::<u16>(1, 2), 4);
+        assert_eq!(BinaryOp::LShift.apply::<u16>(2, 1), 4);
+        assert_eq!(BinaryOp::LShift.apply::<u16>(3, 4), 48);
+        assert_eq!(BinaryOp::LShift.apply::<u16>(123, 0), 123);
+        assert_eq!(BinaryOp::LShift.apply::<u16>(0x8001, 1), 0x0002);
+        assert_eq!(BinaryOp::LShift.apply::<u16>(1, 15), 0x8000);
+        assert_eq!(BinaryOp::LShift.apply::<u16>(1, 16), 0);
+        assert_eq!(BinaryOp::LShift.apply::<u16>(1, 0xFFFF), 0);
+    }
+
+    #[test]
+    fn rshift() {
+        assert_eq!(BinaryOp::RShift.apply::<u16>(4, 2), 1);
+        assert_eq!(BinaryOp::RShift.apply::<u16>(2, 4), 0);
+        assert_eq!(BinaryOp::RShift.apply::<u16>(456, 2), 114);
+        assert_eq!(BinaryOp::RShift.apply::<u16>(123, 0), 123);
+        assert_eq!(BinaryOp::RShift.apply::<u16>(0x8000, 15), 1);
+        assert_eq!(BinaryOp::RShift.apply::<u16>(0xFFFF, 16), 0);
+    }
+
+    #[test]
+    fn other_widths() {
+        assert_eq!(UnaryOp::Not.apply::<u8>(0), 0xFF);
+        assert_eq!(BinaryOp::LShift.apply::<u8>(1, 7), 0x80);
+        assert_eq!(BinaryOp::LShift.apply::<u8>(1, 8), 0);
+        assert_eq!(BinaryOp::RShift.apply::<u8>(0x80, 255), 0);
+
+        assert_eq!(UnaryOp::Not.apply::<u32>(0), 0xFFFF_FFFF);
+        assert_eq!(BinaryOp::LShift.apply::<u32>(1, 16), 0x1_0000);
+        assert_eq!(BinaryOp::LShift.apply::<u32>(1, 32), 0);
+
+        assert_eq!(UnaryOp::Not.apply::<u64>(0), u64::MAX);
+        assert_eq!(BinaryOp::LShift.apply::<u64>(1, 63), 1 << 63);
+        assert_eq!(BinaryOp::RShift.apply::<u64>(1 << 63, 63), 1);
+        // A shift amount too large for a u32 still shifts everything out.
+        assert_eq!(BinaryOp::RShift.apply::<u64>(u64::MAX, 1 << 40), 0);
+        assert_eq!(BinaryOp::And.apply::<u64>(u64::MAX, 1 << 40), 1 << 40);
+        assert_eq!(BinaryOp::Or.apply::<u64>(1 << 40, 1), (1 << 40) | 1);
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for s in [
+            "123 -> x",
+            "x -> y",
+            "x AND y -> d",
+            "1 AND y -> d",
+            "x OR 0 -> e",
+            "NOT y -> e",
+            "NOT 1 -> e",
+            "x LSHIFT 2 -> f",
+            "y RSHIFT 2 -> g",
+        ]
+        .iter()
+        {
+            let a: Box<Assignment> = parse(s).unwrap();
+            assert_eq!(a.to_string(), *s);
+            assert_eq!(parse(&a.to_string()).unwrap(), a);
+        }
+    }
 }
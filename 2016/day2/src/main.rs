@@ -1,68 +1,171 @@
-// Returns relative move for x, y
-fn compute_move(s: &str, start_x: i32, start_y: i32) -> (i32, i32) {
-	//println!("compute_move: s={}, x={}, y={}", s, start_x, start_y);
-	let mut ex = start_x;
-	let mut why = start_y;
-
-	for c in s.chars() {
-		let (x, y) = to_tuple(c);
-		ex += x;
-		why += y;
-
-		ex = match ex {
-			ex if ex > 1 => 1,
-			ex if ex < -1 => -1,
-			_ => ex
-		};
-		why = match why {
-			why if why > 1 => 1,
-			why if why < -1 => -1,
-			_ => why
-		};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::process;
+
+// The keypad from part one.
+const SQUARE: &str = "123\n456\n789";
+
+// The keypad from part two.  Blanks are holes in the keypad.
+const DIAMOND: &str = "  1\n 234\n56789\n ABC\n  D";
+
+#[derive(Debug)]
+enum Error {
+	Io(String, std::io::Error),
+	UnknownDirection { line: usize, column: usize, c: char },
+	NoStartKey,
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Error::Io(path, e) => write!(f, "{}: {}", path, e),
+			Error::UnknownDirection { line, column, c } => write!(
+				f,
+				"line {}, column {}: invalid direction: {}",
+				line, column, c
+			),
+			Error::NoStartKey => write!(f, "keypad has no 5 key to start on"),
+		}
 	}
+}
 
-	(ex, why)
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Io(_, e) => Some(e),
+			_ => None,
+		}
+	}
 }
 
-fn to_tuple(c: char) -> (i32, i32) {
-	match c {
-    	'U' => (0, 1),
-    	'D' => (0, -1),
-    	'L' => (-1, 0),
-    	'R' => (1, 0),
-		_ => panic!("invalid direction: {}", c),
+// A keypad laid out as a grid of keys, with holes where a finger
+// cannot rest.
+struct Keypad {
+	keys: Vec<Vec<Option<char>>>,
+}
+
+impl Keypad {
+	// Each line of the layout is a row of keys; spaces are holes.
+	fn new(layout: &str) -> Keypad {
+		let keys = layout
+			.lines()
+			.map(|row| row.chars().map(|c| if c == ' ' { None } else { Some(c) }).collect())
+			.collect();
+		Keypad { keys }
+	}
+
+	fn key(&self, row: i32, col: i32) -> Option<char> {
+		if row < 0 || col < 0 {
+			return None;
+		}
+		*self.keys.get(row as usize)?.get(col as usize)?
+	}
+
+	fn find(&self, key: char) -> Option<(i32, i32)> {
+		for (row, keys) in self.keys.iter().enumerate() {
+			for (col, k) in keys.iter().enumerate() {
+				if *k == Some(key) {
+					return Some((row as i32, col as i32));
+				}
+			}
+		}
+		None
+	}
+
+	// Follow each line of instructions from where the last left off,
+	// starting at the 5 key, and return the key each ends on.  A move
+	// that would leave the keypad is ignored.
+	fn code(&self, instructions: &str) -> Result<String, Error> {
+		let (mut row, mut col) = self.find('5').ok_or(Error::NoStartKey)?;
+		let mut key = '5';
+		let mut code = String::new();
+
+		for (n, line) in instructions.lines().enumerate() {
+			for (i, c) in line.chars().enumerate() {
+				let (dr, dc) = to_tuple(c).ok_or(Error::UnknownDirection {
+					line: n + 1,
+					column: i + 1,
+					c,
+				})?;
+				if let Some(k) = self.key(row + dr, col + dc) {
+					row += dr;
+					col += dc;
+					key = k;
+				}
+			}
+			code.push(key);
+		}
+
+		Ok(code)
 	}
 }
 
-fn to_num(x: i32, y: i32) -> i32 {
-	match (x, y) {
-    	(-1, 1) => 1,
-    	(0, 1) => 2,
-    	(1, 1) => 3,
-    	(-1, 0) => 4,
-    	(0, 0) => 5,
-    	(1, 0) => 6,
-    	(-1, -1) => 7,
-    	(0, -1) => 8,
-    	(1, -1) => 9,
-		_ => panic!("invalid tuple: x={}, y={}", x, y)
+// Returns the relative move for a direction, as (row, column).
+fn to_tuple(c: char) -> Option<(i32, i32)> {
+	match c {
+		'U' => Some((-1, 0)),
+		'D' => Some((1, 0)),
+		'L' => Some((0, -1)),
+		'R' => Some((0, 1)),
+		_ => None,
 	}
 }
 
+fn run(path: &str) -> Result<(), Error> {
+	let s = fs::read_to_string(path).map_err(|e| Error::Io(path.to_owned(), e))?;
+	let s = s.trim();
+
+	println!("square code={}", Keypad::new(SQUARE).code(s)?);
+	println!("diamond code={}", Keypad::new(DIAMOND).code(s)?);
+	Ok(())
+}
+
 fn main() {
-	let ex1 = ["ULL", "RRDDD", "LURDL", "UUUUD"];
-	let _numpad = [[1, 2, 3], [4, 5, 6], [7, 8, 9]];
-
-	println!("str=U, x=0, y=0, output={:?}", compute_move("U", 0, 0));
-	println!("str=RU, x=0, y=0, output={:?}", compute_move("RU", 0, 0));
-	println!("str=RULD, x=0, y=0, output={:?}", compute_move("RULD", 0, 0));
-
-	let mut x = 0;
-	let mut y = 0;
-	for instr in &ex1 {
-		let (newx, newy) = compute_move(instr, x, y);
-		x = newx;
-		y = newy;
-		println!("digit={}", to_num(x, y));
+	let path = env::args().nth(1).unwrap_or_else(|| "input.txt".to_owned());
+
+	if let Err(e) = run(&path) {
+		eprintln!("{}", e);
+		process::exit(1);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Error, Keypad, DIAMOND, SQUARE};
+
+	const EXAMPLE: &str = "ULL\nRRDDD\nLURDL\nUUUUD";
+
+	#[test]
+	fn square_keypad() {
+		assert_eq!(Keypad::new(SQUARE).code(EXAMPLE).unwrap(), "1985");
+		assert_eq!(Keypad::new(SQUARE).code("U\nRU\nRULD").unwrap(), "235");
+	}
+
+	#[test]
+	fn diamond_keypad() {
+		assert_eq!(Keypad::new(DIAMOND).code(EXAMPLE).unwrap(), "5DB3");
+		// The holes beside the 5 key cannot be stepped into.
+		assert_eq!(Keypad::new(DIAMOND).code("U\nL\nRD").unwrap(), "55A");
+	}
+
+	#[test]
+	fn unknown_direction() {
+		match Keypad::new(SQUARE).code("UL\nRX") {
+			Err(Error::UnknownDirection { line, column, c }) => {
+				assert_eq!((line, column, c), (2, 2, 'X'));
+			}
+			_ => panic!("expected an unknown direction"),
+		}
+	}
+
+	#[test]
+	fn no_start_key() {
+		let keypad = Keypad::new("123\n4 6\n789");
+		assert!(matches!(keypad.code("U"), Err(Error::NoStartKey)));
+		assert_eq!(
+			Error::NoStartKey.to_string(),
+			"keypad has no 5 key to start on"
+		);
 	}
 }
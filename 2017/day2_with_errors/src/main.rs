@@ -1,95 +1,161 @@
+use spreadsheet::Spreadsheet;
+use std::env;
+use std::fmt;
 use std::fs;
+use std::io;
+use std::process;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 enum Error {
-    EmptyRow,
-    FileNotFound(String),
+    /// The spreadsheet could not be read.
+    Io { path: String, source: io::Error },
+    /// A row was left out of a checksum.
+    Sheet(spreadsheet::Error),
 }
 
-impl From<std::io::Error> for Error {
-    fn from(_val:std::io::Error) -> Self {
-        Error::FileNotFound("Not Found".to_owned())
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, .. } => write!(f, "could not read {}", path),
+            Error::Sheet(_) => write!(f, "skipped a row"),
+        }
     }
 }
 
-fn process_row(row: &str) -> Result<u64, Error> {
-    let mut min: u64 = std::u64::MAX;
-    let mut max: u64 = std::u64::MIN;
-
-    if row.is_empty() {
-        return Err(Error::EmptyRow); // note the semicolon here
-    }
-
-    for term in row.split_whitespace() {
-        let term = term.parse::<u64>().unwrap();
-        if term < min {
-            min = term;
-        }
-        if term > max {
-            max = term;
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Sheet(e) => Some(e),
         }
     }
-    let diff = max - min;
-    println!("{}", diff);
-
-    //diff
-    Ok(diff)
 }
 
-fn main() -> Result<(), Error> {
-    let s = fs::read_to_string("foo.txt")?;
-    let s = s.trim();
+impl From<spreadsheet::Error> for Error {
+    fn from(e: spreadsheet::Error) -> Self {
+        Error::Sheet(e)
+    }
+}
 
-    let sum = s.split('\n').map(|x| process_row(x).unwrap()).sum::<u64>();
+fn read(path: &str) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|source| Error::Io {
+        path: path.to_owned(),
+        source,
+    })
+}
 
-    /*
-    let mut sum = 0;
-    for term in s.split('\n').map(|row| process_row(row)) {
-        sum = sum + term;
+// Print the error along with the chain of errors that caused it.
+fn report(e: &dyn std::error::Error) {
+    eprint!("error: {}", e);
+    let mut source = e.source();
+    while let Some(s) = source {
+        eprint!(": {}", s);
+        source = s.source();
     }
-    */
+    eprintln!();
+}
 
-    println!("{}", sum);
-    return Ok(());
+/// The checksums of every row that can be parsed, along with the
+/// errors for those that cannot.  A row without an evenly divisible
+/// pair still counts towards the first checksum.
+fn checksums(s: &str) -> (u64, u64, Vec<Error>) {
+    let (sheet, mut errors) = Spreadsheet::parse_lossy(s);
+    let (divisible, more) = sheet.divisible_checksum_lossy();
+    errors.extend(more);
+    errors.sort_by_key(|e| e.line());
+
+    let errors = errors.into_iter().map(Error::from).collect();
+    (sheet.checksum(), divisible, errors)
+}
 
+fn main() {
+    let path = env::args().nth(1).unwrap_or_else(|| "input.txt".to_owned());
+    let s = match read(&path) {
+        Ok(s) => s,
+        Err(e) => {
+            report(&e);
+            process::exit(1);
+        }
+    };
 
-    /*
-    if let Ok(s) = fs::read_to_string("foo.txt") {
-        let s = s.trim();
+    // Only trailing whitespace is dropped, so that rows keep the line
+    // numbers of the file.
+    let (checksum, divisible, errors) = checksums(s.trim_end());
+    for e in errors.iter() {
+        report(e);
+    }
 
-        let sum = s.split('\n').map(|x| process_row(x).unwrap()).sum::<u64>();
+    println!("{}", checksum);
+    println!("{}", divisible);
 
-        /*
-        let mut sum = 0;
-        for term in s.split('\n').map(|row| process_row(row)) {
-            sum = sum + term;
-        }
-        */
-
-        println!("{}", sum);
-        return Ok(());
+    if !errors.is_empty() {
+        process::exit(1);
     }
-    Err(Error::FileNotFound)
-    */
-    Ok(())
 }
 
 #[cfg(test)]
 // namespace, similar to being in a different file
 mod test {
-    // use super::process_row;
-    // use crate::process_row;
-    use super::Error;
+    use super::{checksums, read, Error};
+    use spreadsheet::{parse_row, row_range};
+    use std::error::Error as _;
 
     #[test]
-     fn test_one() {
-         let input = "5 10 15 20";
-         assert_eq!(crate::process_row(input), Ok(15));
-     }
+    fn test_one() {
+        let input = "5 10 15 20";
+        assert_eq!(parse_row(1, input).map(|row| row_range(&row)), Ok(15));
+    }
 
-     #[test]
-     fn test_empty() {
+    #[test]
+    fn test_empty() {
         let empty_input = "";
-        assert_eq!(crate::process_row(empty_input), Err(Error::EmptyRow));
-     }
+        let e: Error = parse_row(1, empty_input).unwrap_err().into();
+        assert!(matches!(
+            e,
+            Error::Sheet(spreadsheet::Error::EmptyRow { line: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_bad_rows() {
+        let (checksum, divisible, errors) = checksums("5 9 2 8\n\n9 x 7 3\n5 7\n3 8 6 5");
+        assert_eq!(checksum, 7 + 2 + 5);
+        assert_eq!(divisible, 4 + 2);
+
+        let errors: Vec<String> = errors
+            .iter()
+            .map(|e| format!("{}: {}", e, e.source().unwrap()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "skipped a row: line 2: empty row",
+                "skipped a row: line 3, cell 2: not a number: x",
+                "skipped a row: line 4: no cell evenly divides another",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_leading_blank_lines() {
+        // Rows are numbered from the first line of the file, blank or
+        // not.
+        let (checksum, _, errors) = checksums("\n\n5 1 9 5\n7 5 3");
+        assert_eq!(checksum, 8 + 4);
+        let lines: Vec<usize> = errors
+            .iter()
+            .map(|e| match e {
+                Error::Sheet(e) => e.line(),
+                Error::Io { .. } => 0,
+            })
+            .collect();
+        assert_eq!(lines, vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_io() {
+        let e = read("/no/such/file").unwrap_err();
+        assert_eq!(e.to_string(), "could not read /no/such/file");
+        assert!(e.source().is_some());
+    }
 }
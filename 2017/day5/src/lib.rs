@@ -1,56 +1,175 @@
 //! Advent of Code 2017 Day 5
 
-// FIXME: Allow unused stuff while we are working.
-#![allow(dead_code)]
+pub mod trace;
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+use trace::{Step, Tracer};
+
+/// How an offset changes after its instruction jumps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    /// Every offset increases by one.
+    Increment,
+    /// Offsets of three or more decrease by one; others increase by one.
+    Strange,
+}
+
+/// The reasons a program cannot be loaded or run to completion.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The program's file could not be read.
+    Io { path: String, kind: io::ErrorKind },
+    /// The line, numbered from 1, is not an offset.
+    Parse { line: usize, text: String },
+    /// The program was still running after this many steps.
+    StepLimit(usize),
+    /// The trace could not be written.
+    Trace(io::ErrorKind),
+    /// The jump, or the change to its offset, at this step, counting
+    /// from 0, does not fit in an `i32`.
+    Overflow { step: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, kind } => write!(f, "could not read {}: {}", path, kind),
+            Error::Parse { line, text } => write!(f, "line {}: invalid offset: {}", line, text),
+            Error::StepLimit(limit) => write!(f, "still running after {} steps", limit),
+            Error::Trace(kind) => write!(f, "could not write trace: {}", kind),
+            Error::Overflow { step } => write!(f, "step {}: offset overflows", step),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
 
 /// The evaluation state.
-#[derive(Debug, Default)]
-struct State {
+#[derive(Debug)]
+pub struct State {
     /// Our current position in the program.
     exec_ptr: i32,
-    /// The number of jumps in our program.
-    base: usize,
     /// The program that we are evaluating.
     program: Vec<i32>,
     /// The number of steps that we have progressed.
     num_steps: usize,
+    /// How offsets change as they are used.
+    rule: Rule,
+    /// The most steps to take before giving up, if any.
+    max_steps: Option<usize>,
 }
 
-impl State {
-    fn new(path: &str) -> Self {
-        let s = std::fs::read_to_string(path).expect("valid path");
-        let s = s.trim();
-        let base: usize = s.lines().count();
-        let program: Vec<i32> = s.lines().map(|l| l.parse::<i32>().unwrap()).collect();
-        dbg!(&program);
+impl From<&[i32]> for State {
+    fn from(program: &[i32]) -> Self {
         Self {
             exec_ptr: 0,
-            base,
-            program,
+            program: program.to_vec(),
             num_steps: 0,
+            rule: Rule::Increment,
+            max_steps: None,
         }
     }
+}
+
+impl FromStr for State {
+    type Err = Error;
 
-    fn tick(&mut self) {
-        self.num_steps += 1;
-        let instr = self
-            .program
-            .get_mut(self.exec_ptr as usize)
-            .expect("exec_ptr in bounds");
-        self.exec_ptr += *instr;
-        *instr += 1;
+    /// Parse a program of one offset per line.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let program = s
+            .trim()
+            .lines()
+            .enumerate()
+            .map(|(n, l)| {
+                l.trim().parse::<i32>().map_err(|_| Error::Parse {
+                    line: n + 1,
+                    text: l.to_owned(),
+                })
+            })
+            .collect::<Result<Vec<i32>, Error>>()?;
+        Ok(Self::from(program.as_slice()))
     }
+}
 
-    pub fn evaluate(mut self) -> usize {
-        while self.exec_ptr >= 0 && self.exec_ptr < self.base as i32 {
-            // dbg!(&self);
-            self.tick();
+impl State {
+    /// Use the rule to change offsets after each jump.
+    pub fn with_rule(mut self, rule: Rule) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    /// Give up once the program has taken this many steps without
+    /// jumping outside of itself.
+    pub fn with_step_limit(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    fn run<T: Tracer>(&mut self, tracer: &mut T) -> Result<usize, Error> {
+        loop {
+            let ptr = self.exec_ptr;
+            let program = &mut self.program;
+            let instr = match usize::try_from(ptr).ok().and_then(|i| program.get_mut(i)) {
+                Some(instr) => instr,
+                None => return Ok(self.num_steps),
+            };
+            if let Some(limit) = self.max_steps {
+                if self.num_steps >= limit {
+                    return Err(Error::StepLimit(limit));
+                }
+            }
+
+            let read = *instr;
+            let step = self.num_steps;
+            let target = ptr.checked_add(read).ok_or(Error::Overflow { step })?;
+            let written = match self.rule {
+                Rule::Strange if read >= 3 => Some(read - 1),
+                _ => read.checked_add(1),
+            }
+            .ok_or(Error::Overflow { step })?;
+
+            self.num_steps += 1;
+            self.exec_ptr = target;
+            *instr = written;
+            tracer
+                .record(Step {
+                    exec_ptr: ptr,
+                    read,
+                    written: *instr,
+                })
+                .map_err(|e| Error::Trace(e.kind()))?;
         }
-        self.num_steps
     }
+
+    /// Run the program until it jumps outside of itself, returning the
+    /// number of steps taken.
+    pub fn evaluate(mut self) -> Result<usize, Error> {
+        self.run(&mut ())
+    }
+
+    /// Run the program as `evaluate` does, telling the tracer about
+    /// each tick as it happens.  Ticks already taken stay traced if the
+    /// program hits its step limit.
+    pub fn evaluate_traced<T: Tracer>(mut self, tracer: &mut T) -> Result<usize, Error> {
+        self.run(tracer)
+    }
+}
+
+/// Load the program in the file and run it under the rule, returning
+/// the number of steps taken.
+pub fn run_file(path: &str, rule: Rule) -> Result<usize, Error> {
+    let s = fs::read_to_string(path).map_err(|e| Error::Io {
+        path: path.to_owned(),
+        kind: e.kind(),
+    })?;
+    let state: State = s.parse()?;
+    state.with_rule(rule).evaluate()
 }
 
 pub fn foo(path: &str) -> u32 {
-    let state = State::new(path);
-    state.evaluate() as u32
+    run_file(path, Rule::Increment).expect("program runs") as u32
 }
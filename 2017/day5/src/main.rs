@@ -0,0 +1,67 @@
+use day5::trace::{Binary, Csv, Stats};
+use day5::{Rule, State};
+
+use std::env;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+const USAGE: &str = "usage: day5 [--strange] [--trace <file> [--binary]] <program>";
+
+// Run the program, writing each tick to the trace file if one is given
+// and summarising the run afterwards.
+fn run(state: State, trace: Option<&str>, binary: bool) -> Result<(), Box<dyn Error>> {
+    let path = match trace {
+        Some(path) => path,
+        None => {
+            println!("steps={}", state.evaluate()?);
+            return Ok(());
+        }
+    };
+
+    let mut w = BufWriter::new(File::create(path)?);
+    let mut stats = Stats::default();
+    let steps = if binary {
+        state.evaluate_traced(&mut (Binary(&mut w), &mut stats))
+    } else {
+        let mut csv = Csv::new(&mut w)?;
+        state.evaluate_traced(&mut (&mut csv, &mut stats))
+    };
+    w.flush()?;
+
+    eprintln!("{}", stats.summary(5));
+    println!("steps={}", steps?);
+    Ok(())
+}
+
+// Usage: day5 [--strange] [--trace <file> [--binary]] <program>
+//
+// Run the jump program, incrementing each offset after it is used, or
+// with --strange decrementing offsets of three or more instead.  With
+// --trace every tick is written to the file as CSV, or with --binary
+// as twelve bytes per tick, and a summary of the run is printed.
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut rule = Rule::Increment;
+    let mut trace = None;
+    let mut binary = false;
+    let mut program = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strange" => rule = Rule::Strange,
+            "--trace" => trace = Some(args.next().ok_or(USAGE)?),
+            "--binary" => binary = true,
+            _ if !arg.starts_with("--") && program.is_none() => program = Some(arg),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let program = program.ok_or(USAGE)?;
+    if binary && trace.is_none() {
+        return Err(USAGE.into());
+    }
+
+    let s = fs::read_to_string(&program).map_err(|e| format!("reading {}: {}", program, e))?;
+    let state: State = s.parse()?;
+    run(state.with_rule(rule), trace.as_deref(), binary)
+}
@@ -0,0 +1,166 @@
+//! Recording what a jump program did as it ran.
+//!
+//! A [`Tracer`] is told about each tick as it happens, so a trace can
+//! be streamed to a file, summarised, or both, without keeping every
+//! tick in memory.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+
+/// A single tick of the program.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    /// The instruction executed.
+    pub exec_ptr: i32,
+    /// The offset jumped by.
+    pub read: i32,
+    /// The offset left in its place.
+    pub written: i32,
+}
+
+impl Step {
+    /// The instruction jumped to, which may be outside the program, or
+    /// None if it does not fit in an `i32`.
+    pub fn target(&self) -> Option<i32> {
+        self.exec_ptr.checked_add(self.read)
+    }
+}
+
+/// Something told about each tick of a program as it runs.
+pub trait Tracer {
+    fn record(&mut self, step: Step) -> io::Result<()>;
+}
+
+/// Ignore every tick.
+impl Tracer for () {
+    fn record(&mut self, _: Step) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Keep every tick in memory.
+impl Tracer for Vec<Step> {
+    fn record(&mut self, step: Step) -> io::Result<()> {
+        self.push(step);
+        Ok(())
+    }
+}
+
+/// Tell both tracers about each tick.
+impl<A: Tracer, B: Tracer> Tracer for (A, B) {
+    fn record(&mut self, step: Step) -> io::Result<()> {
+        self.0.record(step)?;
+        self.1.record(step)
+    }
+}
+
+impl<T: Tracer + ?Sized> Tracer for &mut T {
+    fn record(&mut self, step: Step) -> io::Result<()> {
+        (**self).record(step)
+    }
+}
+
+/// Write each tick as a row of CSV, after a header row.
+pub struct Csv<W> {
+    w: W,
+    steps: usize,
+}
+
+impl<W: Write> Csv<W> {
+    pub fn new(mut w: W) -> io::Result<Self> {
+        writeln!(w, "step,exec_ptr,read,written")?;
+        Ok(Csv { w, steps: 0 })
+    }
+
+    /// The writer the rows went to.
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
+
+impl<W: Write> Tracer for Csv<W> {
+    fn record(&mut self, s: Step) -> io::Result<()> {
+        writeln!(
+            self.w,
+            "{},{},{},{}",
+            self.steps, s.exec_ptr, s.read, s.written
+        )?;
+        self.steps += 1;
+        Ok(())
+    }
+}
+
+/// Write each tick as twelve bytes: the pointer, offset read and offset
+/// written, each a little-endian `i32`.
+pub struct Binary<W>(pub W);
+
+impl<W: Write> Tracer for Binary<W> {
+    fn record(&mut self, s: Step) -> io::Result<()> {
+        self.0.write_all(&s.exec_ptr.to_le_bytes())?;
+        self.0.write_all(&s.read.to_le_bytes())?;
+        self.0.write_all(&s.written.to_le_bytes())
+    }
+}
+
+/// Count the ticks as they happen, to summarise them afterwards.
+#[derive(Debug, Default)]
+pub struct Stats {
+    steps: usize,
+    max_ptr: Option<i32>,
+    counts: HashMap<i32, usize>,
+}
+
+impl Tracer for Stats {
+    fn record(&mut self, s: Step) -> io::Result<()> {
+        self.steps += 1;
+        *self.counts.entry(s.exec_ptr).or_insert(0) += 1;
+        let furthest = s.target().map_or(s.exec_ptr, |t| t.max(s.exec_ptr));
+        self.max_ptr = Some(self.max_ptr.map_or(furthest, |p| p.max(furthest)));
+        Ok(())
+    }
+}
+
+/// Statistics gathered from a run.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    /// The number of ticks.
+    pub steps: usize,
+    /// The furthest instruction executed or jumped to, if any were.
+    /// This includes the final jump out of the program.
+    pub max_ptr: Option<i32>,
+    /// The most executed instructions and how often each was executed,
+    /// most executed first.
+    pub hottest: Vec<(i32, usize)>,
+}
+
+impl Stats {
+    /// Summarise the ticks so far, keeping the `top` most executed
+    /// instructions.  Ties go to the earlier instruction.
+    pub fn summary(&self, top: usize) -> Summary {
+        let mut hottest: Vec<(i32, usize)> = self.counts.iter().map(|(p, n)| (*p, *n)).collect();
+        hottest.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        hottest.truncate(top);
+
+        Summary {
+            steps: self.steps,
+            max_ptr: self.max_ptr,
+            hottest,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "steps: {}", self.steps)?;
+        match self.max_ptr {
+            Some(p) => writeln!(f, "max pointer: {}", p)?,
+            None => writeln!(f, "max pointer: none")?,
+        }
+        write!(f, "hottest:")?;
+        for (ptr, count) in self.hottest.iter() {
+            write!(f, "\n  {:>6} x{}", ptr, count)?;
+        }
+        Ok(())
+    }
+}
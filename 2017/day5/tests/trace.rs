@@ -0,0 +1,144 @@
+use day5::trace::{Binary, Csv, Stats, Step, Summary};
+use day5::{Error, Rule, State};
+
+const SMOL: &[i32] = &[0, 3, 0, 1, -3];
+
+#[test]
+fn records_each_tick() {
+    let mut steps = Vec::new();
+    let result = State::from(SMOL).evaluate_traced(&mut steps);
+    assert_eq!(result, Ok(5));
+    assert_eq!(
+        steps,
+        vec![
+            Step {
+                exec_ptr: 0,
+                read: 0,
+                written: 1
+            },
+            Step {
+                exec_ptr: 0,
+                read: 1,
+                written: 2
+            },
+            Step {
+                exec_ptr: 1,
+                read: 3,
+                written: 4
+            },
+            Step {
+                exec_ptr: 4,
+                read: -3,
+                written: -2
+            },
+            Step {
+                exec_ptr: 1,
+                read: 4,
+                written: 5
+            },
+        ]
+    );
+
+    let mut steps = Vec::new();
+    State::from(SMOL)
+        .with_rule(Rule::Strange)
+        .evaluate_traced(&mut steps)
+        .unwrap();
+    assert_eq!(
+        steps[2],
+        Step {
+            exec_ptr: 1,
+            read: 3,
+            written: 2
+        }
+    );
+}
+
+#[test]
+fn kept_past_step_limit() {
+    let mut steps = Vec::new();
+    let result = State::from(SMOL)
+        .with_step_limit(3)
+        .evaluate_traced(&mut steps);
+    assert_eq!(result, Err(Error::StepLimit(3)));
+    assert_eq!(steps.len(), 3);
+}
+
+#[test]
+fn csv_and_binary() {
+    let mut csv = Csv::new(Vec::new()).unwrap();
+    let mut bin = Binary(Vec::new());
+    State::from(SMOL)
+        .evaluate_traced(&mut (&mut csv, &mut bin))
+        .unwrap();
+
+    let csv = String::from_utf8(csv.into_inner()).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0], "step,exec_ptr,read,written");
+    assert_eq!(lines[4], "3,4,-3,-2");
+
+    assert_eq!(bin.0.len(), 5 * 12);
+    assert_eq!(
+        &bin.0[36..48],
+        &[4, 0, 0, 0, 253, 255, 255, 255, 254, 255, 255, 255]
+    );
+}
+
+#[test]
+fn trace_write_errors() {
+    struct Full;
+    impl std::io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::WriteZero.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    assert_eq!(
+        State::from(SMOL).evaluate_traced(&mut Binary(Full)),
+        Err(Error::Trace(std::io::ErrorKind::WriteZero))
+    );
+}
+
+#[test]
+fn summary() {
+    let mut stats = Stats::default();
+    State::from(SMOL).evaluate_traced(&mut stats).unwrap();
+    let summary = stats.summary(2);
+    // The last jump, from 1 by 4, leaves the program at 5.
+    assert_eq!(
+        summary,
+        Summary {
+            steps: 5,
+            max_ptr: Some(5),
+            hottest: vec![(0, 2), (1, 2)],
+        }
+    );
+    assert_eq!(
+        summary.to_string(),
+        "steps: 5\nmax pointer: 5\nhottest:\n       0 x2\n       1 x2"
+    );
+
+    let mut stats = Stats::default();
+    State::from(&[][..]).evaluate_traced(&mut stats).unwrap();
+    assert_eq!(stats.summary(3).max_ptr, None);
+
+    // Jumping off the front leaves the furthest pointer where it was.
+    let mut stats = Stats::default();
+    State::from(&[2, 0, -5][..])
+        .evaluate_traced(&mut stats)
+        .unwrap();
+    assert_eq!(stats.summary(1).max_ptr, Some(2));
+
+    // A target past i32::MAX is left out of the furthest pointer.
+    let step = Step {
+        exec_ptr: 1,
+        read: i32::MAX,
+        written: i32::MAX - 1,
+    };
+    assert_eq!(step.target(), None);
+    assert_eq!(Step { read: 4, ..step }.target(), Some(5));
+}
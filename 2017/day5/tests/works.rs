@@ -1,4 +1,4 @@
-use day5::foo;
+use day5::{foo, run_file, Error, Rule, State};
 
 #[test]
 fn it_might_work() {
@@ -14,3 +14,80 @@ fn left_bounds() {
 fn it_works() {
     assert_eq!(foo("tests/fixtures/input.txt"), 358131);
 }
+
+#[test]
+fn strange_jumps() {
+    assert_eq!(
+        run_file("tests/fixtures/smol-input.txt", Rule::Strange),
+        Ok(10)
+    );
+    assert_eq!(
+        run_file("tests/fixtures/input.txt", Rule::Strange),
+        Ok(25558839)
+    );
+}
+
+#[test]
+fn run_file_errors() {
+    assert_eq!(
+        run_file("tests/fixtures/no-such-file.txt", Rule::Increment),
+        Err(Error::Io {
+            path: "tests/fixtures/no-such-file.txt".to_owned(),
+            kind: std::io::ErrorKind::NotFound
+        })
+    );
+}
+
+#[test]
+fn from_slice_and_str() {
+    let program: &[i32] = &[0, 3, 0, 1, -3];
+    assert_eq!(State::from(program).evaluate(), Ok(5));
+    assert_eq!(
+        "0\n3\n0\n1\n-3\n".parse::<State>().unwrap().evaluate(),
+        Ok(5)
+    );
+    assert_eq!(
+        "0\n3\nthree".parse::<State>().unwrap_err(),
+        Error::Parse {
+            line: 3,
+            text: "three".to_owned()
+        }
+    );
+}
+
+#[test]
+fn step_limit() {
+    let program: &[i32] = &[0, 3, 0, 1, -3];
+    let state = || State::from(program).with_rule(Rule::Strange);
+    assert_eq!(state().with_step_limit(10).evaluate(), Ok(10));
+    assert_eq!(
+        state().with_step_limit(9).evaluate(),
+        Err(Error::StepLimit(9))
+    );
+
+    // Every program eventually leaves, but may take a long time to.
+    let s = std::fs::read_to_string("tests/fixtures/input.txt").unwrap();
+    let state: State = s.parse().unwrap();
+    assert_eq!(
+        state.with_step_limit(1000).evaluate(),
+        Err(Error::StepLimit(1000))
+    );
+}
+
+#[test]
+fn overflow() {
+    // The offset cannot be incremented past i32::MAX.
+    assert_eq!(
+        "2147483647".parse::<State>().unwrap().evaluate(),
+        Err(Error::Overflow { step: 0 })
+    );
+    // The jump from 1 by i32::MAX lands past i32::MAX.
+    assert_eq!(
+        "0\n2147483647".parse::<State>().unwrap().evaluate(),
+        Err(Error::Overflow { step: 2 })
+    );
+    assert_eq!(
+        Error::Overflow { step: 2 }.to_string(),
+        "step 2: offset overflows"
+    );
+}
@@ -0,0 +1,244 @@
+//! Spreadsheets of unsigned integers, as used by 2017's day 2.
+
+use std::fmt;
+
+/// The reasons a spreadsheet cannot be read or checksummed.  Lines are
+/// numbered from 1, as are the cells within a row.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The row has no cells.
+    EmptyRow { line: usize },
+    /// The cell is not an unsigned integer.
+    NonNumeric {
+        line: usize,
+        cell: usize,
+        text: String,
+    },
+    /// No cell in the row evenly divides another.
+    NoDivisiblePair { line: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::EmptyRow { line } => write!(f, "line {}: empty row", line),
+            Error::NonNumeric { line, cell, text } => {
+                write!(f, "line {}, cell {}: not a number: {}", line, cell, text)
+            }
+            Error::NoDivisiblePair { line } => {
+                write!(f, "line {}: no cell evenly divides another", line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// The line the error was found on.
+    pub fn line(&self) -> usize {
+        match self {
+            Error::EmptyRow { line }
+            | Error::NonNumeric { line, .. }
+            | Error::NoDivisiblePair { line } => *line,
+        }
+    }
+}
+
+/// A spreadsheet of unsigned integers.  Rows need not all have the
+/// same number of cells.
+#[derive(Debug, PartialEq)]
+pub struct Spreadsheet {
+    pub rows: Vec<Vec<u64>>,
+    // The line each row was read from, for reporting errors.
+    lines: Vec<usize>,
+}
+
+/// Parse a single row of cells separated by tabs or spaces.  `line` is
+/// the row's line number, used to report errors.
+pub fn parse_row(line: usize, row: &str) -> Result<Vec<u64>, Error> {
+    let cells = row
+        .split_whitespace()
+        .enumerate()
+        .map(|(i, cell)| {
+            cell.parse::<u64>().map_err(|_| Error::NonNumeric {
+                line,
+                cell: i + 1,
+                text: cell.to_owned(),
+            })
+        })
+        .collect::<Result<Vec<u64>, Error>>()?;
+
+    if cells.is_empty() {
+        return Err(Error::EmptyRow { line });
+    }
+    Ok(cells)
+}
+
+/// The difference between the largest and smallest cells in the row.
+pub fn row_range(row: &[u64]) -> u64 {
+    match (row.iter().max(), row.iter().min()) {
+        (Some(max), Some(min)) => max - min,
+        _ => 0,
+    }
+}
+
+/// The result of dividing the first cell in the row that another cell
+/// evenly divides by that cell.
+pub fn row_quotient(row: &[u64]) -> Option<u64> {
+    for (i, a) in row.iter().enumerate() {
+        for (j, b) in row.iter().enumerate() {
+            if i != j && *b != 0 && a % b == 0 {
+                return Some(a / b);
+            }
+        }
+    }
+    None
+}
+
+impl Spreadsheet {
+    /// Parse one row per line, reporting every row that cannot be
+    /// parsed.
+    pub fn parse(s: &str) -> Result<Self, Vec<Error>> {
+        let (sheet, errors) = Spreadsheet::parse_lossy(s);
+        if errors.is_empty() {
+            Ok(sheet)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Parse one row per line, keeping every row that can be parsed
+    /// and returning the errors for those that cannot.
+    pub fn parse_lossy(s: &str) -> (Self, Vec<Error>) {
+        let mut sheet = Spreadsheet {
+            rows: Vec::new(),
+            lines: Vec::new(),
+        };
+        let mut errors = Vec::new();
+
+        for (n, line) in s.lines().enumerate() {
+            match parse_row(n + 1, line) {
+                Ok(row) => {
+                    sheet.rows.push(row);
+                    sheet.lines.push(n + 1);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        (sheet, errors)
+    }
+
+    /// The sum of each row's largest cell less its smallest.
+    pub fn checksum(&self) -> u64 {
+        self.rows.iter().map(|row| row_range(row)).sum()
+    }
+
+    /// The sum of each row's quotient of the only two cells where one
+    /// evenly divides the other.
+    pub fn divisible_checksum(&self) -> Result<u64, Error> {
+        self.rows
+            .iter()
+            .zip(self.lines.iter())
+            .map(|(row, line)| row_quotient(row).ok_or(Error::NoDivisiblePair { line: *line }))
+            .sum()
+    }
+
+    /// The sum of the quotients of every row that has an evenly
+    /// divisible pair, along with the errors for the rows that do not.
+    pub fn divisible_checksum_lossy(&self) -> (u64, Vec<Error>) {
+        let mut sum = 0;
+        let mut errors = Vec::new();
+
+        for (row, line) in self.rows.iter().zip(self.lines.iter()) {
+            match row_quotient(row) {
+                Some(q) => sum += q,
+                None => errors.push(Error::NoDivisiblePair { line: *line }),
+            }
+        }
+
+        (sum, errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_row, row_quotient, row_range, Error, Spreadsheet};
+
+    #[test]
+    fn parse_rows() {
+        assert_eq!(parse_row(1, "5\t1 9  5"), Ok(vec![5, 1, 9, 5]));
+        assert_eq!(parse_row(3, " \t"), Err(Error::EmptyRow { line: 3 }));
+        assert_eq!(
+            parse_row(2, "5\t1x 9"),
+            Err(Error::NonNumeric {
+                line: 2,
+                cell: 2,
+                text: "1x".to_owned()
+            })
+        );
+
+        assert_eq!(
+            Spreadsheet::parse("1 2\n\n3 -4").unwrap_err(),
+            vec![
+                Error::EmptyRow { line: 2 },
+                Error::NonNumeric {
+                    line: 3,
+                    cell: 2,
+                    text: "-4".to_owned()
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn checksum() {
+        assert_eq!(row_range(&[5, 10, 15, 20]), 15);
+        let s = Spreadsheet::parse("5 1 9 5\n7 5 3\n2 4 6 8").unwrap();
+        assert_eq!(s.checksum(), 18);
+    }
+
+    #[test]
+    fn divisible_checksum() {
+        assert_eq!(row_quotient(&[3, 8, 6, 5]), Some(2));
+        assert_eq!(row_quotient(&[5, 7, 3]), None);
+        assert_eq!(row_quotient(&[0]), None);
+        let s = Spreadsheet::parse("5 9 2 8\n9 4 7 3\n3 8 6 5").unwrap();
+        assert_eq!(s.divisible_checksum(), Ok(9));
+
+        let s = Spreadsheet::parse("4 2\n5 7").unwrap();
+        assert_eq!(
+            s.divisible_checksum(),
+            Err(Error::NoDivisiblePair { line: 2 })
+        );
+    }
+
+    #[test]
+    fn parse_lossy() {
+        let (s, errors) = Spreadsheet::parse_lossy("\n5 9 2 8\n9 x 7 3\n5 7\n3 8 6 5");
+        assert_eq!(s.rows, vec![vec![5, 9, 2, 8], vec![5, 7], vec![3, 8, 6, 5]]);
+        assert_eq!(
+            errors,
+            vec![
+                Error::EmptyRow { line: 1 },
+                Error::NonNumeric {
+                    line: 3,
+                    cell: 2,
+                    text: "x".to_owned()
+                }
+            ]
+        );
+        assert_eq!(s.checksum(), 7 + 2 + 5);
+
+        // Rows keep the lines they were read from.
+        assert_eq!(
+            s.divisible_checksum_lossy(),
+            (4 + 2, vec![Error::NoDivisiblePair { line: 4 }])
+        );
+        assert_eq!(
+            s.divisible_checksum(),
+            Err(Error::NoDivisiblePair { line: 4 })
+        );
+    }
+}
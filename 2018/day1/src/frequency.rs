@@ -0,0 +1,125 @@
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The line, numbered from 1, is not a signed change.
+    Malformed { line: usize, text: String },
+    /// Cycling through the changes never reaches a frequency twice.
+    NeverRepeats,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Malformed { line, text } => {
+                write!(
+                    f,
+                    "line {}: expected a change like +3 or -7: {}",
+                    line, text
+                )
+            }
+            Error::NeverRepeats => write!(f, "no frequency is ever reached twice"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Parse one change per line.  Each must begin with its sign.
+pub fn parse(s: &str) -> Result<Vec<i64>, Error> {
+    s.lines()
+        .enumerate()
+        .map(|(n, line)| {
+            let malformed = || Error::Malformed {
+                line: n + 1,
+                text: line.to_owned(),
+            };
+            let digits = match line.get(1..) {
+                Some(d) if line.starts_with('+') || line.starts_with('-') => d,
+                _ => return Err(malformed()),
+            };
+            if !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(malformed());
+            }
+            line.parse::<i64>().map_err(|_| malformed())
+        })
+        .collect()
+}
+
+/// The frequency after every change, starting from zero.
+pub fn final_frequency(changes: &[i64]) -> i64 {
+    changes.iter().sum()
+}
+
+/// The first frequency reached twice when applying the changes over
+/// and over, starting from zero.
+///
+/// The frequencies of each pass are those of the first shifted by the
+/// drift of a whole pass, so two can only ever meet if they are equal
+/// modulo the drift.  When none are, the changes never repeat.
+pub fn first_repeat(changes: &[i64]) -> Result<i64, Error> {
+    if changes.is_empty() {
+        return Err(Error::NeverRepeats);
+    }
+
+    let drift = final_frequency(changes);
+    if drift != 0 {
+        let mut residues = HashSet::new();
+        let mut frequency: i64 = 0;
+        let mut congruent = false;
+        for c in changes.iter() {
+            congruent |= !residues.insert(frequency.rem_euclid(drift.abs()));
+            frequency += c;
+        }
+        if !congruent {
+            return Err(Error::NeverRepeats);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut frequency = 0;
+    for c in changes.iter().cycle() {
+        if !seen.insert(frequency) {
+            return Ok(frequency);
+        }
+        frequency += c;
+    }
+    unreachable!("cycling a non-empty list never ends")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{final_frequency, first_repeat, parse, Error};
+
+    #[test]
+    fn parse_changes() {
+        assert_eq!(parse("+1\n-2\n+3"), Ok(vec![1, -2, 3]));
+        for bad in ["1", "+", " +1", "+-1", "- 1", "+1x"].iter() {
+            assert_eq!(
+                parse(&format!("+1\n{}", bad)),
+                Err(Error::Malformed {
+                    line: 2,
+                    text: bad.to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn final_and_repeat() {
+        assert_eq!(final_frequency(&[1, -2, 3, 1]), 3);
+        assert_eq!(first_repeat(&[1, -2, 3, 1]), Ok(2));
+        assert_eq!(first_repeat(&[1, -1]), Ok(0));
+        assert_eq!(first_repeat(&[3, 3, 4, -2, -4]), Ok(10));
+        assert_eq!(first_repeat(&[-6, 3, 8, 5, -6]), Ok(5));
+        assert_eq!(first_repeat(&[7, 7, -2, -7, -4]), Ok(14));
+    }
+
+    #[test]
+    fn never_repeats() {
+        assert_eq!(first_repeat(&[1, 1, 1]), Err(Error::NeverRepeats));
+        assert_eq!(first_repeat(&[5, -2]), Err(Error::NeverRepeats));
+        assert_eq!(first_repeat(&[]), Err(Error::NeverRepeats));
+    }
+}
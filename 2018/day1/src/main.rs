@@ -1,39 +1,34 @@
-use std::error::Error;
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
+mod frequency;
+
+use std::env;
+use std::fs;
+use std::process;
 
 fn main() {
-    // Create a path to the desired file
-    let path = Path::new("hello.txt");
-    let display = path.display();
-    let mut result = 0;
+    let path = env::args().nth(1).unwrap_or_else(|| "hello.txt".to_owned());
 
-    // Open the path in read-only mode, returns `io::Result<File>`
-    let mut file = match File::open(&path) {
-        // The `description` method of `io::Error` returns a string that
-        // describes the error
-        Err(why) => panic!("couldn't open {}: {}", display, why.description()),
-        Ok(file) => file,
+    let s = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(why) => {
+            eprintln!("couldn't read {}: {}", path, why);
+            process::exit(1);
+        }
     };
 
-    // Read the file contents into a string, returns `io::Result<usize>`
-    let mut s = String::new();
-    match file.read_to_string(&mut s) {
-        Err(why) => panic!("couldn't read {}: {}", display, why.description()),
-        Ok(_) => (),
-    }
-
-    for s in s.trim_end().split("\n") {
-        let eos = &s[1..];
-        let val = eos.parse::<i32>().unwrap();
+    let changes = match frequency::parse(s.trim_end()) {
+        Ok(changes) => changes,
+        Err(why) => {
+            eprintln!("{}: {}", path, why);
+            process::exit(1);
+        }
+    };
 
-        if s.starts_with("+") {
-            result = result + val;
-        } else if s.starts_with("-") {
-            result = result - val;
+    println!("{}", frequency::final_frequency(&changes));
+    match frequency::first_repeat(&changes) {
+        Ok(f) => println!("{}", f),
+        Err(why) => {
+            eprintln!("{}: {}", path, why);
+            process::exit(1);
         }
     }
-
-    println!("{}", result);
 }
@@ -0,0 +1,147 @@
+use std::collections::{HashMap, HashSet};
+
+/// The number of IDs with some letter exactly twice, times the number
+/// with some letter exactly three times.
+pub fn checksum(ids: &[&str]) -> usize {
+    let mut twos = 0;
+    let mut threes = 0;
+
+    for id in ids.iter() {
+        let mut letters = HashMap::new();
+        for c in id.chars() {
+            *letters.entry(c).or_insert(0) += 1;
+        }
+        if letters.values().any(|n| *n == 2) {
+            twos += 1;
+        }
+        if letters.values().any(|n| *n == 3) {
+            threes += 1;
+        }
+    }
+
+    twos * threes
+}
+
+/// The letters two IDs have in common, in the same position.
+pub fn common_letters(a: &str, b: &str) -> String {
+    a.chars()
+        .zip(b.chars())
+        .filter(|(x, y)| x == y)
+        .map(|(x, _)| x)
+        .collect()
+}
+
+fn distance(a: &[char], b: &[char]) -> usize {
+    a.iter().zip(b.iter()).filter(|(x, y)| x != y).count()
+}
+
+/// The indices of the first two IDs that differ in exactly one
+/// position.  Each ID is hashed once for every position with that
+/// position masked out; two IDs differing only there hash the same.
+pub fn near_duplicate(ids: &[&str]) -> Option<(usize, usize)> {
+    let mut masked: HashMap<(usize, String, String), usize> = HashMap::new();
+
+    for (i, id) in ids.iter().enumerate() {
+        let chars: Vec<char> = id.chars().collect();
+        for p in 0..chars.len() {
+            let key = (
+                p,
+                chars[..p].iter().collect(),
+                chars[p + 1..].iter().collect(),
+            );
+            match masked.get(&key) {
+                // Identical IDs share every mask but differ nowhere.
+                Some(j) if ids[*j] != *id => return Some((*j, i)),
+                Some(_) => {}
+                None => {
+                    masked.insert(key, i);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Every pair of IDs, by index, that are the same length and differ in
+/// at most `k` positions.  Each pair is listed once, lower index first,
+/// in order.
+///
+/// Split into `k + 1` blocks, two such IDs must agree on at least one
+/// whole block, so only IDs sharing a block are compared.
+pub fn within_distance(ids: &[&str], k: usize) -> Vec<(usize, usize)> {
+    let chars: Vec<Vec<char>> = ids.iter().map(|id| id.chars().collect()).collect();
+    let mut blocks: HashMap<(usize, usize, &[char]), Vec<usize>> = HashMap::new();
+
+    for (i, id) in chars.iter().enumerate() {
+        let len = id.len();
+        for b in 0..=k {
+            let block = &id[b * len / (k + 1)..(b + 1) * len / (k + 1)];
+            blocks.entry((len, b, block)).or_default().push(i);
+        }
+    }
+
+    let mut pairs = HashSet::new();
+    for candidates in blocks.values() {
+        for (n, i) in candidates.iter().enumerate() {
+            for j in candidates[n + 1..].iter() {
+                if distance(&chars[*i], &chars[*j]) <= k {
+                    pairs.insert((*i, *j));
+                }
+            }
+        }
+    }
+
+    let mut pairs: Vec<(usize, usize)> = pairs.into_iter().collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checksum, common_letters, near_duplicate, within_distance};
+
+    #[test]
+    fn checksum_counts() {
+        let ids = [
+            "abcdef", "bababc", "abbcde", "abcccd", "aabcdd", "abcdee", "ababab",
+        ];
+        assert_eq!(checksum(&ids), 12);
+    }
+
+    #[test]
+    fn near_duplicates() {
+        let ids = [
+            "abcde", "fghij", "klmno", "pqrst", "fguij", "axcye", "wvxyz",
+        ];
+        assert_eq!(near_duplicate(&ids), Some((1, 4)));
+        assert_eq!(common_letters(ids[1], ids[4]), "fgij");
+
+        assert_eq!(near_duplicate(&["abc", "abc", "xyz"]), None);
+        assert_eq!(near_duplicate(&["abc", "abcd", "abd"]), Some((0, 2)));
+    }
+
+    #[test]
+    fn pairs_within_distance() {
+        let ids = ["abcd", "abcx", "abyx", "zbcd", "abcd", "abc", "wxyz"];
+        assert_eq!(within_distance(&ids, 0), vec![(0, 4)]);
+        assert_eq!(
+            within_distance(&ids, 1),
+            vec![(0, 1), (0, 3), (0, 4), (1, 2), (1, 4), (3, 4)]
+        );
+
+        // Every pair the blocks find is one comparing all pairs finds.
+        for k in 0..=4 {
+            let mut all = Vec::new();
+            for i in 0..ids.len() {
+                for j in i + 1..ids.len() {
+                    let (a, b) = (ids[i], ids[j]);
+                    let d = a.chars().zip(b.chars()).filter(|(x, y)| x != y).count();
+                    if a.len() == b.len() && d <= k {
+                        all.push((i, j));
+                    }
+                }
+            }
+            assert_eq!(within_distance(&ids, k), all, "k = {}", k);
+        }
+    }
+}
@@ -1,57 +1,126 @@
-use anyhow::{anyhow, Result};
+mod sweep;
+
+use anyhow::{anyhow, Context, Result};
 use regex::Regex;
+use std::env;
 use std::fs;
+use std::str::FromStr;
+
+/// A claim on a rectangle of fabric, in square inches from the top
+/// left corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Claim {
+    pub id: u32,
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+}
+
+impl FromStr for Claim {
+    type Err = anyhow::Error;
+
+    ///   Note that a claim looks like:
+    ///     `#1 @ 179,662: 16x27`
+    ///
+    ///    Or more generically:
+    ///     `#<id>` `@` `top-x`,`top-y`: `width`x`length`
+    fn from_str(s: &str) -> Result<Self> {
+        let re = Regex::new(r"^#(\d+) @ (\d+),(\d+): (\d+)x(\d+)$")?;
+
+        // The captures method returns an option, so one cannot use the ?
+        // operator on it directly.  Instead, use the ok_or_else method to
+        // convert an option into the kind of return value this function
+        // needs to return.
+        let cap = re
+            .captures(s.trim())
+            .ok_or_else(|| anyhow!("invalid claim string: {}", s))?;
+
+        Ok(Claim {
+            id: cap[1].parse()?,
+            x: cap[2].parse()?,
+            y: cap[3].parse()?,
+            w: cap[4].parse()?,
+            h: cap[5].parse()?,
+        })
+    }
+}
+
+/// Parse one claim per line.
+pub fn parse_claims(s: &str) -> Result<Vec<Claim>> {
+    s.lines()
+        .enumerate()
+        .map(|(n, line)| line.parse().with_context(|| format!("line {}", n + 1)))
+        .collect()
+}
 
-#[derive(Default)] // If we derive the Default Trait here...declaration is easier.
 /// The Elves need to make Santa a special suit from some special fabric.
 pub struct Fabric {
-    pub inches: [[u32; 10]; 10], // This could be a vector!  :)
+    /// How many claims ask for each square inch, row by row.
+    pub inches: Vec<Vec<u32>>,
 }
 
 impl Fabric {
+    /// A piece of fabric just large enough for every claim.
+    pub fn new(claims: &[Claim]) -> Self {
+        let width = claims.iter().map(|c| c.x + c.w).max().unwrap_or(0);
+        let height = claims.iter().map(|c| c.y + c.h).max().unwrap_or(0);
+        Fabric {
+            inches: vec![vec![0; width]; height],
+        }
+    }
+
     pub fn pretty_print(&self) {
         for row in self.inches.iter() {
             for inch in row.iter() {
                 print!("{}", inch);
             }
-            println!("");
+            println!();
         }
     }
-}
 
-///   Given a piece of fabric and a string containing a collection of claims,
-///   parse the string and indicate on the fabric how many claims are asking
-///   for each square inch of fabric.
-///
-///   Note that a claim looks like:
-///     `#1 @ 179,662: 16x27`
-///
-///    Or more generically:
-///     `<id>` `@` `top-x`,`top-y`: `width`x`length`
-pub fn process(fabric: &mut Fabric, s: &str) -> Result<()> {
-    // There are two crates called anyhow and thiserror.  If you were to put
-    // a
-    let re = Regex::new(r".?@ (\d{1,3}),(\d{1,3}): (\d{1,3})x(\d{1,3})")?;
-
-    for claim in s.split("\n") {
-        // The captures method returns an option, so one cannot use the ?
-        // operator on it directly.  Instead, use the ok_or method to convert
-        // an option into the kind of return value this process function
-        // needs to return.
-        let cap = re.captures(claim).ok_or(anyhow!("invalid claim string"))?;
+    /// The square inches of the claim.
+    fn area(&self, claim: Claim) -> impl Iterator<Item = &u32> {
+        self.inches[claim.y..claim.y + claim.h]
+            .iter()
+            .flat_map(move |row| row[claim.x..claim.x + claim.w].iter())
+    }
 
-        let x: usize = cap[1].parse()?;
-        let y: usize = cap[2].parse()?;
-        let width: usize = cap[3].parse()?;
-        let height: usize = cap[4].parse()?;
+    /// The number of square inches within two or more claims.
+    pub fn overlap(&self) -> usize {
+        self.inches.iter().flatten().filter(|n| **n >= 2).count()
+    }
 
-        for i in x..x + width {
-            for j in y..y + height {
-                fabric.inches[i][j] += 1;
+    /// The ID of the first claim that overlaps no other.
+    pub fn intact(&self, claims: &[Claim]) -> Option<u32> {
+        self.isolated(claims).first().copied()
+    }
+
+    /// The IDs of every claim that overlaps no other, in the order
+    /// given.
+    pub fn isolated(&self, claims: &[Claim]) -> Vec<u32> {
+        claims
+            .iter()
+            .filter(|c| self.area(**c).all(|n| *n == 1))
+            .map(|c| c.id)
+            .collect()
+    }
+}
+
+///   Given a collection of claims, lay them on a piece of fabric large
+///   enough to hold them all and indicate on the fabric how many claims
+///   are asking for each square inch of fabric.
+pub fn process(claims: &[Claim]) -> Fabric {
+    let mut fabric = Fabric::new(claims);
+
+    for claim in claims.iter() {
+        for row in fabric.inches[claim.y..claim.y + claim.h].iter_mut() {
+            for inch in row[claim.x..claim.x + claim.w].iter_mut() {
+                *inch += 1;
             }
         }
     }
-    Ok(())
+    fabric
 }
 
 // Rust offers the ability to derive Traits.  Let's say we'd like to
@@ -64,7 +133,17 @@ fn main() -> Result<()> {
     // There might be another function that you could call that's lines() that
     // might prevent you from reading it all in at once; instead you could
     // read it line-by-line.  This may not matter for this puzzle.
-    let s = fs::read_to_string("claims.txt")?;
+    //
+    // Usage: day3 [--sweep] [claims file]
+    let mut sweep = false;
+    let mut path = "claims.txt".to_owned();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--sweep" => sweep = true,
+            _ => path = arg,
+        }
+    }
+    let s = fs::read_to_string(&path).with_context(|| format!("reading {}", path))?;
 
     // If claims.txt is not found, the program prints out:
     //  Error: Os { code: 2, kind: NotFound, message: "No such file or directory" }
@@ -72,16 +151,13 @@ fn main() -> Result<()> {
     // use a match statement.  But the ? is stopping the program and printing
     // the error for us.
 
-    let s = s.trim();
-    let mut fabric: Fabric = Default::default();
+    let claims = parse_claims(s.trim())?;
 
     // There aren't constructors in Rust.  There's no `new` keyword.
     // By convention, you just do a function called new, and usually there would
     // be parameters like lxw.
     // It's how I listen.
 
-    fabric.pretty_print();
-
     // The person who wrote the Regex crate is called "BurntSushi".
     // There's another useful crate called `ripgrep` that is like super fast grep.
     // That's a crate that's fun to learn about.
@@ -92,12 +168,61 @@ fn main() -> Result<()> {
     // It's better to get out of main quickly.
     // Call something like "dothestuff" function. Let's move our code
     // into a function and move the error conversion up a level.
-    process(&mut fabric, s)?;
+    let (overlap, intact) = if sweep {
+        (
+            sweep::overlap(&claims),
+            sweep::isolated(&claims).first().copied(),
+        )
+    } else {
+        let fabric = process(&claims);
+
+        // Real claims cover a square yard or so, too much to print.
+        if fabric.inches.len() <= 40 {
+            fabric.pretty_print();
+        }
+        (fabric.overlap(), fabric.intact(&claims))
+    };
 
-    fabric.pretty_print();
+    println!("overlap={}", overlap);
+    match intact {
+        Some(id) => println!("intact=#{}", id),
+        None => println!("every claim overlaps another"),
+    }
 
     // This wraps nothing inside of an Ok.  To specify a nothing object,
     // one uses ().  The thing that is okay is nothing, which isn't
     // really returning anything.
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_claims, process, Claim};
+
+    #[test]
+    fn parse() {
+        let claims = parse_claims("#1 @ 179,662: 16x27\n#12 @ 3,2: 5x4").unwrap();
+        assert_eq!(
+            claims[1],
+            Claim {
+                id: 12,
+                x: 3,
+                y: 2,
+                w: 5,
+                h: 4
+            }
+        );
+        assert!(parse_claims("#1 @ 1,3: 4x4\n@ 3,1: 4x4").is_err());
+    }
+
+    #[test]
+    fn overlap_and_intact() {
+        let claims = parse_claims("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2").unwrap();
+        let fabric = process(&claims);
+        assert_eq!(fabric.inches.len(), 7);
+        assert_eq!(fabric.inches[0].len(), 7);
+        assert_eq!(fabric.overlap(), 4);
+        assert_eq!(fabric.intact(&claims), Some(3));
+        assert_eq!(fabric.isolated(&claims), vec![3]);
+    }
+}
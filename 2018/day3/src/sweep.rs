@@ -0,0 +1,147 @@
+use crate::Claim;
+
+/// The number of square inches within two or more claims.
+///
+/// The fabric is swept left to right, with each claim joining the
+/// sweep at its left edge and leaving at its right.  The claims' top
+/// and bottom edges cut the fabric into horizontal bands, and the sweep
+/// keeps how many of the claims in play cover each band, along with
+/// the total height of the bands covered twice or more.  Between two
+/// edges that height times the distance swept is overlapping area.
+pub fn overlap(claims: &[Claim]) -> usize {
+    let mut ys: Vec<usize> = claims.iter().flat_map(|c| vec![c.y, c.y + c.h]).collect();
+    ys.sort_unstable();
+    ys.dedup();
+    let band = |y: usize| ys.partition_point(|b| *b < y);
+
+    let mut edges: Vec<(usize, i32, &Claim)> = claims
+        .iter()
+        .flat_map(|c| vec![(c.x, 1, c), (c.x + c.w, -1, c)])
+        .collect();
+    edges.sort_unstable_by_key(|(x, _, _)| *x);
+
+    let mut depth = vec![0; ys.len().saturating_sub(1)];
+    let mut doubled = 0;
+    let mut area = 0;
+    let mut last = 0;
+    for (x, delta, c) in edges {
+        area += (x - last) * doubled;
+        last = x;
+
+        for b in band(c.y)..band(c.y + c.h) {
+            let height = ys[b + 1] - ys[b];
+            let before = depth[b] >= 2;
+            depth[b] += delta;
+            match (before, depth[b] >= 2) {
+                (false, true) => doubled += height,
+                (true, false) => doubled -= height,
+                _ => {}
+            }
+        }
+    }
+    area
+}
+
+// Whether the claims share a square inch.  A claim without any area
+// overlaps nothing, even where it lies inside another.
+fn overlaps(a: &Claim, b: &Claim) -> bool {
+    let empty = |c: &Claim| c.w == 0 || c.h == 0;
+    !empty(a)
+        && !empty(b)
+        && a.x < b.x + b.w
+        && b.x < a.x + a.w
+        && a.y < b.y + b.h
+        && b.y < a.y + a.h
+}
+
+/// The IDs of every claim that overlaps no other, in the order given.
+///
+/// Claims are taken left to right; each need only be compared with
+/// those that begin before it ends.
+pub fn isolated(claims: &[Claim]) -> Vec<u32> {
+    let mut order: Vec<usize> = (0..claims.len()).collect();
+    order.sort_unstable_by_key(|i| claims[*i].x);
+
+    let mut alone = vec![true; claims.len()];
+    for (n, i) in order.iter().enumerate() {
+        let a = &claims[*i];
+        for j in order[n + 1..].iter() {
+            let b = &claims[*j];
+            if b.x >= a.x + a.w {
+                break;
+            }
+            if overlaps(a, b) {
+                alone[*i] = false;
+                alone[*j] = false;
+            }
+        }
+    }
+
+    claims
+        .iter()
+        .zip(alone.iter())
+        .filter(|(_, alone)| **alone)
+        .map(|(c, _)| c.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{isolated, overlap};
+    use crate::{parse_claims, process, Claim};
+
+    #[test]
+    fn example() {
+        let claims = parse_claims("#1 @ 1,3: 4x4\n#2 @ 3,1: 4x4\n#3 @ 5,5: 2x2").unwrap();
+        assert_eq!(overlap(&claims), 4);
+        assert_eq!(isolated(&claims), vec![3]);
+        assert_eq!(overlap(&[]), 0);
+
+        // Claims that only touch, or share an edge with an empty
+        // claim, do not overlap.
+        let claims = parse_claims("#1 @ 0,0: 2x2\n#2 @ 2,0: 2x2\n#3 @ 1,1: 0x5").unwrap();
+        assert_eq!(overlap(&claims), 0);
+        assert_eq!(isolated(&claims), vec![1, 2, 3]);
+    }
+
+    // A small linear congruential generator, so the test needs no
+    // crates and always draws the same claims.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn below(&mut self, n: u64) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((self.0 >> 33) % n) as usize
+        }
+    }
+
+    #[test]
+    fn matches_grid() {
+        let mut rng = Lcg(2018);
+        for round in 0..500 {
+            let count = 1 + rng.below(12);
+            let size = 1 + rng.below(30) as u64;
+            let claims: Vec<Claim> = (0..count)
+                .map(|id| Claim {
+                    id: id as u32 + 1,
+                    x: rng.below(size),
+                    y: rng.below(size),
+                    w: rng.below(size / 2 + 1),
+                    h: rng.below(size / 2 + 1),
+                })
+                .collect();
+
+            let fabric = process(&claims);
+            assert_eq!(overlap(&claims), fabric.overlap(), "round {}", round);
+            assert_eq!(
+                isolated(&claims),
+                fabric.isolated(&claims),
+                "round {}",
+                round
+            );
+        }
+    }
+}
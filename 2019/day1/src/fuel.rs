@@ -0,0 +1,90 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum Error {
+    #[error("line {line}: invalid mass: {text}")]
+    InvalidMass { line: usize, text: String },
+    #[error("line {line}: mass {mass} is too small to need fuel")]
+    ValueTooSmall { line: usize, mass: u32 },
+}
+
+/// The fuel needed to lift the mass, if it is large enough to need any.
+pub fn fuel(mass: u32) -> Option<u32> {
+    (mass / 3).checked_sub(2)
+}
+
+/// The fuel needed to lift the mass and all of the fuel itself, until
+/// the last of it is too small to need any more.
+pub fn fuel_for_fuel(mass: u32) -> u32 {
+    let mut total = 0;
+    let mut mass = mass;
+    while let Some(f) = fuel(mass) {
+        total += f;
+        mass = f;
+    }
+    total
+}
+
+/// Parse one module mass per line.  A module must be heavy enough to
+/// need fuel.
+pub fn parse(s: &str) -> Result<Vec<u32>, Error> {
+    s.lines()
+        .enumerate()
+        .map(|(n, l)| {
+            let mass = l.trim().parse::<u32>().map_err(|_| Error::InvalidMass {
+                line: n + 1,
+                text: l.to_owned(),
+            })?;
+            match fuel(mass) {
+                Some(_) => Ok(mass),
+                None => Err(Error::ValueTooSmall { line: n + 1, mass }),
+            }
+        })
+        .collect()
+}
+
+/// The fuel for every module, not counting the fuel's own mass.
+pub fn simple(masses: &[u32]) -> u32 {
+    masses.iter().filter_map(|m| fuel(*m)).sum()
+}
+
+/// The fuel for every module, counting the fuel's own mass.
+pub fn recursive(masses: &[u32]) -> u32 {
+    masses.iter().map(|m| fuel_for_fuel(*m)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuel, fuel_for_fuel, parse, recursive, simple, Error};
+
+    #[test]
+    fn simple_fuel() {
+        assert_eq!(fuel(12), Some(2));
+        assert_eq!(fuel(14), Some(2));
+        assert_eq!(fuel(1969), Some(654));
+        assert_eq!(fuel(100756), Some(33583));
+        assert_eq!(fuel(5), None);
+        assert_eq!(simple(&[12, 14, 1969, 100756]), 2 + 2 + 654 + 33583);
+    }
+
+    #[test]
+    fn recursive_fuel() {
+        assert_eq!(fuel_for_fuel(14), 2);
+        assert_eq!(fuel_for_fuel(1969), 966);
+        assert_eq!(fuel_for_fuel(100756), 50346);
+        assert_eq!(recursive(&[14, 1969]), 968);
+    }
+
+    #[test]
+    fn bad_masses() {
+        assert_eq!(parse("12\n6\n"), Ok(vec![12, 6]));
+        assert_eq!(
+            parse("12\n2").unwrap_err(),
+            Error::ValueTooSmall { line: 2, mass: 2 }
+        );
+        assert_eq!(
+            parse("12\n-3").unwrap_err().to_string(),
+            "line 2: invalid mass: -3"
+        );
+    }
+}
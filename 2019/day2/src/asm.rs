@@ -0,0 +1,300 @@
+use crate::disasm::MNEMONICS;
+use crate::intcode::{Mode, OpCode};
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Why a line of assembly could not be assembled.
+#[derive(Debug, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+#[derive(Debug)]
+enum Value {
+    Int(i64),
+    Label(String),
+}
+
+// An instruction, or with no opcode, data cells.
+struct Statement {
+    line: usize,
+    op: Option<OpCode>,
+    operands: Vec<(Mode, Value)>,
+}
+
+// Whether the name may label an address.  `rb` names the relative
+// base, so is not a label.
+fn is_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    s != "rb"
+        && matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// The offset from the relative base, if the inside of the brackets
+// is `rb` alone or followed by a sign.  Labels such as `rbase` are
+// not offsets.
+fn relative(inner: &str) -> Option<&str> {
+    let rest = inner.strip_prefix("rb")?;
+    match rest.chars().next() {
+        None => Some(rest),
+        Some(c) if c == '+' || c == '-' || c.is_whitespace() => Some(rest),
+        Some(_) => None,
+    }
+}
+
+fn value(s: &str) -> Result<Value, String> {
+    if let Ok(v) = s.parse::<i64>() {
+        Ok(Value::Int(v))
+    } else if is_label(s) {
+        Ok(Value::Label(s.to_owned()))
+    } else {
+        Err(format!("invalid operand `{}`", s))
+    }
+}
+
+fn operand(s: &str) -> Result<(Mode, Value), String> {
+    let s = s.trim();
+    match s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        Some(inner) => match relative(inner.trim()) {
+            Some("") => Ok((Mode::Relative, Value::Int(0))),
+            Some(offset) => {
+                let offset: String = offset.split_whitespace().collect();
+                let offset = offset
+                    .strip_prefix('+')
+                    .unwrap_or(&offset)
+                    .parse::<i64>()
+                    .map_err(|_| format!("invalid relative operand `{}`", s))?;
+                Ok((Mode::Relative, Value::Int(offset)))
+            }
+            None => Ok((Mode::Position, value(inner.trim())?)),
+        },
+        None => Ok((Mode::Immediate, value(s)?)),
+    }
+}
+
+// Parse a line, less any label and address, into a statement.
+fn statement(line: usize, text: &str) -> Result<Statement, String> {
+    let (word, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let operands = if rest.trim().is_empty() {
+        Vec::new()
+    } else {
+        rest.split(',')
+            .map(operand)
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    if word == "data" {
+        if operands.is_empty() {
+            return Err("data needs at least one value".to_owned());
+        }
+        if operands.iter().any(|(mode, _)| *mode != Mode::Immediate) {
+            return Err("data values cannot have a mode".to_owned());
+        }
+        return Ok(Statement {
+            line,
+            op: None,
+            operands,
+        });
+    }
+
+    let op = MNEMONICS
+        .iter()
+        .find(|(_, m)| *m == word)
+        .map(|(op, _)| *op)
+        .ok_or_else(|| format!("unknown mnemonic `{}`", word))?;
+    if operands.len() != op.arity() {
+        return Err(format!(
+            "{} takes {} operands, not {}",
+            word,
+            op.arity(),
+            operands.len()
+        ));
+    }
+    Ok(Statement {
+        line,
+        op: Some(op),
+        operands,
+    })
+}
+
+/// Assemble a program into memory.
+///
+/// Each line holds an instruction, `data` followed by values, or
+/// nothing.  An instruction is a mnemonic followed by its operands,
+/// written as the disassembler lists them: `[a]` for a position, `v`
+/// for an immediate value, and `[rb+o]` for an offset from the relative
+/// base.  A line may begin with `name:` to label its address, which
+/// other lines may then use in place of a number; `rb` is reserved for
+/// the relative base.  A line may also
+/// begin with its address, as in a listing, which must be where the
+/// line is assembled.  Everything after `#` is a comment.
+pub fn assemble(src: &str) -> Result<Vec<i64>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut statements = Vec::new();
+    let mut addr = 0;
+
+    for (n, line) in src.lines().enumerate() {
+        let error = |message: String| AsmError {
+            line: n + 1,
+            message,
+        };
+        let mut text = line.split('#').next().unwrap().trim();
+
+        if let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(error(format!("invalid label `{}`", label)));
+            }
+            if labels.insert(label.to_owned(), addr as i64).is_some() {
+                return Err(error(format!("label `{}` is defined twice", label)));
+            }
+            text = rest.trim();
+        }
+
+        let (word, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        if let Ok(at) = word.parse::<usize>() {
+            if at != addr {
+                return Err(error(format!("listed at {} but assembled at {}", at, addr)));
+            }
+            text = rest.trim();
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+        let s = statement(n + 1, text).map_err(error)?;
+        addr += s.operands.len() + s.op.map_or(0, |_| 1);
+        statements.push(s);
+    }
+
+    let mut memory = Vec::new();
+    for s in statements.iter() {
+        let resolve = |v: &Value| match v {
+            Value::Int(i) => Ok(*i),
+            Value::Label(l) => labels.get(l).copied().ok_or_else(|| AsmError {
+                line: s.line,
+                message: format!("undefined label `{}`", l),
+            }),
+        };
+
+        if let Some(op) = s.op {
+            let mut instr = op.code();
+            let mut place = 100;
+            for (mode, _) in s.operands.iter() {
+                instr += mode.digit() * place;
+                place *= 10;
+            }
+            memory.push(instr);
+        }
+        for (_, v) in s.operands.iter() {
+            memory.push(resolve(v)?);
+        }
+    }
+
+    Ok(memory)
+}
+
+/// Write memory in the comma separated form programs are loaded from.
+pub fn to_comma(memory: &[i64]) -> String {
+    let cells: Vec<String> = memory.iter().map(|v| v.to_string()).collect();
+    cells.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, to_comma, AsmError};
+    use crate::disasm::listing;
+    use crate::intcode::{IntcodeProgram, Status};
+
+    #[test]
+    fn assemble_program() {
+        let src = "
+            # Count down from the input, writing each number.
+                    in   [n]
+            loop:   out  [n]
+                    add  [n], -1, [n]
+                    jt   [n], loop
+                    hlt
+            n:      data 0
+        ";
+        let memory = assemble(src).unwrap();
+        assert_eq!(to_comma(&memory), "3,12,4,12,1001,12,-1,12,1005,12,2,99,0");
+
+        let mut p = IntcodeProgram::new(memory);
+        p.push_input(3);
+        assert_eq!(p.evaluate(), Ok(Status::Halted));
+        assert_eq!(p.output, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn modes() {
+        let memory = assemble("arb 5\nadd [rb-2], [rb], [rb+7]\nmul 3, [4], [5]\nhlt").unwrap();
+        assert_eq!(to_comma(&memory), "109,5,22201,-2,0,7,102,3,4,5,99");
+    }
+
+    #[test]
+    fn listing_round_trips() {
+        for program in [
+            include_str!("../input.txt"),
+            include_str!("../input-big.txt"),
+            "1105,1,5,42,43,1006,0,3,99",
+            "109,-1,204,3,99,21108,8,8,-3",
+        ]
+        .iter()
+        {
+            let memory = IntcodeProgram::parse(program).unwrap().memory;
+            let listed = listing(memory.as_slice());
+            assert_eq!(assemble(&listed).unwrap(), memory.as_slice(), "{}", listed);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src: &str| assemble(src).unwrap_err();
+        assert_eq!(
+            err("hlt\nfoo 1"),
+            AsmError {
+                line: 2,
+                message: "unknown mnemonic `foo`".to_owned()
+            }
+        );
+        assert_eq!(err("add 1, 2").message, "add takes 3 operands, not 2");
+        assert_eq!(err("jt 1, nowhere").message, "undefined label `nowhere`");
+        assert_eq!(err("a: hlt\na: hlt").message, "label `a` is defined twice");
+        assert_eq!(
+            err("out [rb+x]").message,
+            "invalid relative operand `[rb+x]`"
+        );
+        assert_eq!(err("data [3]").message, "data values cannot have a mode");
+        assert_eq!(
+            err("0 hlt\n2 hlt").message,
+            "listed at 2 but assembled at 1"
+        );
+        assert_eq!(err("out 3,").message, "invalid operand ``");
+        assert_eq!(err("rb: hlt").message, "invalid label `rb`");
+        assert_eq!(err("out [rb]\nout rb").message, "invalid operand `rb`");
+    }
+
+    #[test]
+    fn labels_starting_with_rb() {
+        assert_eq!(
+            assemble("out [rbase]\nout [rb - 2]\nout rb_1\nrbase: hlt\nrb_1: data 7"),
+            Ok(vec![4, 6, 204, -2, 104, 7, 99, 7])
+        );
+        assert_eq!(
+            assemble("out [rbx+1]").unwrap_err().message,
+            "invalid operand `rbx+1`"
+        );
+    }
+}
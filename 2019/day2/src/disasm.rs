@@ -0,0 +1,220 @@
+use crate::intcode::{decode, Mode, OpCode};
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The mnemonic for each opcode, as listed and as assembled.
+pub const MNEMONICS: [(OpCode, &str); 10] = [
+    (OpCode::Add, "add"),
+    (OpCode::Mult, "mul"),
+    (OpCode::Input, "in"),
+    (OpCode::Output, "out"),
+    (OpCode::JumpIfTrue, "jt"),
+    (OpCode::JumpIfFalse, "jf"),
+    (OpCode::LessThan, "lt"),
+    (OpCode::Equals, "eq"),
+    (OpCode::AdjustBase, "arb"),
+    (OpCode::Halt, "hlt"),
+];
+
+pub fn mnemonic(op: OpCode) -> &'static str {
+    MNEMONICS.iter().find(|(o, _)| *o == op).unwrap().1
+}
+
+/// What a stretch of memory holds.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Entry {
+    /// An instruction that execution may reach, with the mode and value
+    /// of each of its parameters.
+    Code {
+        op: OpCode,
+        params: Vec<(Mode, i64)>,
+    },
+    /// A cell that is never executed.
+    Data(i64),
+}
+
+/// An entry and the address it starts at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub addr: usize,
+    pub entry: Entry,
+}
+
+// The instruction at the address, if one can be decoded there without
+// running off the end of memory.
+fn instruction(memory: &[i64], addr: usize) -> Option<Entry> {
+    let (op, modes) = decode(*memory.get(addr)?)?;
+    let params = memory.get(addr + 1..addr + 1 + op.arity())?;
+    Some(Entry::Code {
+        op,
+        params: modes.iter().copied().zip(params.iter().copied()).collect(),
+    })
+}
+
+/// The addresses of every instruction reachable from address 0.
+///
+/// Execution is followed from each instruction to the next, and to the
+/// target of each jump whose target is immediate.  A jump whose
+/// condition is immediate is taken either always or never.  Targets
+/// held elsewhere in memory cannot be known without running the
+/// program, so code reached only through them is listed as data.
+pub fn reachable(memory: &[i64]) -> BTreeSet<usize> {
+    let mut starts = BTreeSet::new();
+    let mut pending = vec![0];
+
+    while let Some(addr) = pending.pop() {
+        if starts.contains(&addr) {
+            continue;
+        }
+        let (op, params) = match instruction(memory, addr) {
+            Some(Entry::Code { op, params }) => (op, params),
+            _ => continue,
+        };
+        starts.insert(addr);
+
+        let next = addr + 1 + op.arity();
+        match op {
+            OpCode::Halt => {}
+            OpCode::JumpIfTrue | OpCode::JumpIfFalse => {
+                let taken = match params[0] {
+                    (Mode::Immediate, c) => Some((c != 0) == (op == OpCode::JumpIfTrue)),
+                    _ => None,
+                };
+                if taken != Some(true) {
+                    pending.push(next);
+                }
+                if taken != Some(false) {
+                    if let (Mode::Immediate, target) = params[1] {
+                        if target >= 0 {
+                            pending.push(target as usize);
+                        }
+                    }
+                }
+            }
+            _ => pending.push(next),
+        }
+    }
+
+    starts
+}
+
+/// Split memory into the instructions reachable from address 0 and the
+/// data between them.
+pub fn disassemble(memory: &[i64]) -> Vec<Line> {
+    let starts = reachable(memory);
+    let mut lines = Vec::new();
+    let mut addr = 0;
+
+    while addr < memory.len() {
+        let entry = match instruction(memory, addr) {
+            Some(code) if starts.contains(&addr) => code,
+            _ => Entry::Data(memory[addr]),
+        };
+        let len = match &entry {
+            Entry::Code { params, .. } => 1 + params.len(),
+            Entry::Data(_) => 1,
+        };
+        lines.push(Line { addr, entry });
+        addr += len;
+    }
+
+    lines
+}
+
+/// The listing of memory, one line per entry.
+pub fn listing(memory: &[i64]) -> String {
+    disassemble(memory)
+        .iter()
+        .map(|l| format!("{}\n", l))
+        .collect()
+}
+
+/// Write a parameter as it is assembled: `[a]` for a position, `v` for
+/// an immediate value and `[rb+o]` for an offset from the relative
+/// base.
+pub fn operand(mode: Mode, value: i64) -> String {
+    match mode {
+        Mode::Position => format!("[{}]", value),
+        Mode::Immediate => value.to_string(),
+        Mode::Relative if value < 0 => format!("[rb{}]", value),
+        Mode::Relative => format!("[rb+{}]", value),
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>5}  ", self.addr)?;
+        match &self.entry {
+            Entry::Code { op, params } if params.is_empty() => write!(f, "{}", mnemonic(*op)),
+            Entry::Code { op, params } => {
+                let operands: Vec<String> = params.iter().map(|(m, v)| operand(*m, *v)).collect();
+                write!(f, "{:<4}{}", mnemonic(*op), operands.join(", "))
+            }
+            Entry::Data(value) => write!(f, "data {}", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{disassemble, listing, reachable, Entry};
+    use crate::intcode::{Mode, OpCode};
+
+    fn memory(program: &str) -> Vec<i64> {
+        program.split(',').map(|c| c.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn list_code_and_data() {
+        let m = memory(include_str!("../input.txt").trim());
+        assert_eq!(
+            listing(&m),
+            "    0  add [9], [10], [3]\n\
+             \x20   4  mul [3], [11], [0]\n\
+             \x20   8  hlt\n\
+             \x20   9  data 30\n\
+             \x20  10  data 40\n\
+             \x20  11  data 50\n"
+        );
+
+        let lines = disassemble(&memory("1101,100,-1,4,0,99"));
+        assert_eq!(
+            lines[0].entry,
+            Entry::Code {
+                op: OpCode::Add,
+                params: vec![
+                    (Mode::Immediate, 100),
+                    (Mode::Immediate, -1),
+                    (Mode::Position, 4)
+                ]
+            }
+        );
+        // The halt is only written once the program runs, so is data.
+        assert_eq!(lines[1].entry, Entry::Data(0));
+        assert_eq!(lines[2].to_string(), "    5  data 99");
+        assert_eq!(
+            disassemble(&memory("109,-1,204,3,99"))[1].to_string(),
+            "    2  out [rb+3]"
+        );
+    }
+
+    #[test]
+    fn follow_jumps() {
+        // An unconditional jump over data, and a conditional one into
+        // data.
+        let m = memory("1105,1,5,42,43,1006,0,3,99");
+        let starts: Vec<usize> = reachable(&m).into_iter().collect();
+        assert_eq!(starts, vec![0, 5, 8]);
+
+        // A jump that is never taken, then one always taken to a target
+        // out of range.
+        let m = memory("1106,1,4,1105,1,-4,99");
+        let starts: Vec<usize> = reachable(&m).into_iter().collect();
+        assert_eq!(starts, vec![0, 3]);
+
+        // Anything not decodable is data, even at the start.
+        assert!(reachable(&memory("42,99")).is_empty());
+        assert!(reachable(&memory("1,0,0")).is_empty());
+    }
+}
@@ -0,0 +1,558 @@
+use crate::memory::Memory;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::num::ParseIntError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpCode {
+    Add,
+    Mult,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustBase,
+    Halt,
+}
+
+impl OpCode {
+    /// The number of parameters following the opcode.
+    pub fn arity(self) -> usize {
+        match self {
+            OpCode::Add | OpCode::Mult | OpCode::LessThan | OpCode::Equals => 3,
+            OpCode::JumpIfTrue | OpCode::JumpIfFalse => 2,
+            OpCode::Input | OpCode::Output | OpCode::AdjustBase => 1,
+            OpCode::Halt => 0,
+        }
+    }
+
+    /// The number the opcode is written as, the inverse of
+    /// `int_to_opcode`.
+    pub fn code(self) -> i64 {
+        match self {
+            OpCode::Add => 1,
+            OpCode::Mult => 2,
+            OpCode::Input => 3,
+            OpCode::Output => 4,
+            OpCode::JumpIfTrue => 5,
+            OpCode::JumpIfFalse => 6,
+            OpCode::LessThan => 7,
+            OpCode::Equals => 8,
+            OpCode::AdjustBase => 9,
+            OpCode::Halt => 99,
+        }
+    }
+}
+
+/// How a parameter is turned into a value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// The parameter is the address of the value.
+    Position,
+    /// The parameter is the value.
+    Immediate,
+    /// The parameter plus the relative base is the address of the value.
+    Relative,
+}
+
+impl Mode {
+    /// The digit the mode is written as, the inverse of `int_to_mode`.
+    pub fn digit(self) -> i64 {
+        match self {
+            Mode::Position => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
+}
+
+/// Why the machine stopped after a step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    /// The machine can take another step.
+    Running,
+    /// The next instruction reads input, but none is waiting.  Push
+    /// some and evaluate again to carry on.
+    NeedsInput,
+    /// The machine reached a halt instruction.
+    Halted,
+}
+
+/// The reasons an Intcode machine cannot carry on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VmError {
+    /// The instruction at the address has an unknown opcode or
+    /// parameter mode.
+    BadOpcode { pc: usize, instr: i64 },
+    /// The address is negative or past the limit of memory.
+    AddressOutOfRange(i64),
+    /// The machine halted at the address while still expected to run.
+    UnexpectedHalt { pc: usize },
+    /// The instruction at the address computed a value, or an address,
+    /// too large for an `i64`.
+    Overflow { pc: usize },
+    /// The machine waited for input at the address while expected to
+    /// run to a halt.
+    UnexpectedInput { pc: usize },
+    /// The machine was still running after this many steps.
+    StepLimit(usize),
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmError::BadOpcode { pc, instr } => {
+                write!(f, "invalid instruction {} at address {}", instr, pc)
+            }
+            VmError::AddressOutOfRange(addr) => write!(f, "address {} is out of range", addr),
+            VmError::UnexpectedHalt { pc } => write!(f, "unexpected halt at address {}", pc),
+            VmError::Overflow { pc } => write!(f, "arithmetic overflow at address {}", pc),
+            VmError::UnexpectedInput { pc } => write!(f, "unexpected input at address {}", pc),
+            VmError::StepLimit(limit) => write!(f, "still running after {} steps", limit),
+        }
+    }
+}
+
+impl std::error::Error for VmError {}
+
+/// An Intcode machine.  Memory holds both the program and its data,
+/// and grows, filled with zeros, as addresses past its end are used.
+#[derive(Clone, Debug)]
+pub struct IntcodeProgram {
+    pub pc: usize,
+    pub relative_base: i64,
+    pub memory: Memory,
+    /// Values waiting to be read by input instructions, first to be
+    /// read first.
+    pub input: VecDeque<i64>,
+    /// Values written by output instructions, in order.
+    pub output: Vec<i64>,
+}
+
+impl IntcodeProgram {
+    pub fn new(memory: Vec<i64>) -> Self {
+        Self {
+            pc: 0,
+            relative_base: 0,
+            memory: Memory::new(memory),
+            input: VecDeque::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Load a program written as comma separated integers.
+    pub fn parse(s: &str) -> Result<Self, ParseIntError> {
+        let memory = s
+            .trim()
+            .split(',')
+            .map(|c| c.trim().parse::<i64>())
+            .collect::<Result<Vec<i64>, ParseIntError>>()?;
+        Ok(Self::new(memory))
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    // The address the n-th parameter of the current instruction refers
+    // to.  Immediate parameters refer to where they themselves are
+    // stored.
+    fn address(&self, n: usize, mode: Mode) -> Result<i64, VmError> {
+        let at = (self.pc + n) as i64;
+        match mode {
+            Mode::Position => self.memory.read(at),
+            Mode::Immediate => Ok(at),
+            Mode::Relative => self.overflow(self.relative_base.checked_add(self.memory.read(at)?)),
+        }
+    }
+
+    // The result of the current instruction's arithmetic, if it did not
+    // overflow.
+    fn overflow(&self, value: Option<i64>) -> Result<i64, VmError> {
+        value.ok_or(VmError::Overflow { pc: self.pc })
+    }
+
+    fn param(&self, n: usize, modes: &[Mode; 3]) -> Result<i64, VmError> {
+        self.memory.read(self.address(n, modes[n - 1])?)
+    }
+
+    fn store(&mut self, n: usize, modes: &[Mode; 3], value: i64) -> Result<(), VmError> {
+        let addr = self.address(n, modes[n - 1])?;
+        self.memory.write(addr, value)
+    }
+
+    pub fn evaluate_one_step(&mut self) -> Result<Status, VmError> {
+        let instr = self.memory.read(self.pc as i64)?;
+        let (op, modes) = decode(instr).ok_or(VmError::BadOpcode { pc: self.pc, instr })?;
+        let next = self.pc + 1 + op.arity();
+
+        match op {
+            OpCode::Add => {
+                let sum =
+                    self.overflow(self.param(1, &modes)?.checked_add(self.param(2, &modes)?))?;
+                self.store(3, &modes, sum)?;
+            }
+            OpCode::Mult => {
+                let product =
+                    self.overflow(self.param(1, &modes)?.checked_mul(self.param(2, &modes)?))?;
+                self.store(3, &modes, product)?;
+            }
+            OpCode::Input => match self.input.pop_front() {
+                Some(value) => self.store(1, &modes, value)?,
+                None => return Ok(Status::NeedsInput),
+            },
+            OpCode::Output => {
+                let value = self.param(1, &modes)?;
+                self.output.push(value);
+            }
+            OpCode::JumpIfTrue | OpCode::JumpIfFalse => {
+                let test = self.param(1, &modes)? != 0;
+                if test == (op == OpCode::JumpIfTrue) {
+                    self.pc = Memory::index(self.param(2, &modes)?)?;
+                    return Ok(Status::Running);
+                }
+            }
+            OpCode::LessThan => {
+                let less = self.param(1, &modes)? < self.param(2, &modes)?;
+                self.store(3, &modes, less as i64)?;
+            }
+            OpCode::Equals => {
+                let equal = self.param(1, &modes)? == self.param(2, &modes)?;
+                self.store(3, &modes, equal as i64)?;
+            }
+            OpCode::AdjustBase => {
+                let offset = self.param(1, &modes)?;
+                self.relative_base = self.overflow(self.relative_base.checked_add(offset))?;
+            }
+            OpCode::Halt => return Ok(Status::Halted),
+        }
+
+        self.pc = next;
+        Ok(Status::Running)
+    }
+
+    /// Run until the machine halts or waits for input.
+    pub fn evaluate(&mut self) -> Result<Status, VmError> {
+        loop {
+            match self.evaluate_one_step()? {
+                Status::Running => {}
+                status => return Ok(status),
+            }
+        }
+    }
+
+    /// Run until the machine halts, taking at most `max_steps` steps.
+    /// Waiting for input, or running out of steps, is an error.
+    pub fn run_to_halt(&mut self, max_steps: usize) -> Result<(), VmError> {
+        for _ in 0..max_steps {
+            match self.evaluate_one_step()? {
+                Status::Running => {}
+                Status::NeedsInput => return Err(VmError::UnexpectedInput { pc: self.pc }),
+                Status::Halted => return Ok(()),
+            }
+        }
+        Err(VmError::StepLimit(max_steps))
+    }
+
+    /// Run until the machine writes its next output, and return it, or
+    /// None if the machine needs input first.  Halting first is an
+    /// error.
+    pub fn next_output(&mut self) -> Result<Option<i64>, VmError> {
+        let written = self.output.len();
+        while self.output.len() == written {
+            match self.evaluate_one_step()? {
+                Status::Running => {}
+                Status::NeedsInput => return Ok(None),
+                Status::Halted => return Err(VmError::UnexpectedHalt { pc: self.pc }),
+            }
+        }
+        Ok(self.output.last().copied())
+    }
+}
+
+pub fn int_to_opcode(n: i64) -> Option<OpCode> {
+    match n {
+        1 => Some(OpCode::Add),
+        2 => Some(OpCode::Mult),
+        3 => Some(OpCode::Input),
+        4 => Some(OpCode::Output),
+        5 => Some(OpCode::JumpIfTrue),
+        6 => Some(OpCode::JumpIfFalse),
+        7 => Some(OpCode::LessThan),
+        8 => Some(OpCode::Equals),
+        9 => Some(OpCode::AdjustBase),
+        99 => Some(OpCode::Halt),
+        _ => None,
+    }
+}
+
+pub fn int_to_mode(n: i64) -> Option<Mode> {
+    match n {
+        0 => Some(Mode::Position),
+        1 => Some(Mode::Immediate),
+        2 => Some(Mode::Relative),
+        _ => None,
+    }
+}
+
+/// Split an instruction into its opcode, the last two digits, and the
+/// modes of its parameters, one digit each from the hundreds upwards.
+/// Digits beyond the modes of the opcode's parameters must be zero.
+pub fn decode(instr: i64) -> Option<(OpCode, [Mode; 3])> {
+    if instr < 0 {
+        return None;
+    }
+    let op = int_to_opcode(instr % 100)?;
+    if instr / 10i64.pow(2 + op.arity() as u32) != 0 {
+        return None;
+    }
+    let modes = [
+        int_to_mode(instr / 100 % 10)?,
+        int_to_mode(instr / 1000 % 10)?,
+        int_to_mode(instr / 10000 % 10)?,
+    ];
+    Some((op, modes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntcodeProgram, Status, VmError};
+
+    fn run(program: &str, input: &[i64]) -> IntcodeProgram {
+        let mut p = IntcodeProgram::parse(program).unwrap();
+        for i in input.iter() {
+            p.push_input(*i);
+        }
+        assert_eq!(p.evaluate(), Ok(Status::Halted));
+        p
+    }
+
+    fn at(p: &IntcodeProgram, addr: i64) -> i64 {
+        p.memory.read(addr).unwrap()
+    }
+
+    #[test]
+    fn day2() {
+        let p = run(include_str!("../input.txt"), &[]);
+        assert_eq!(at(&p, 0), 3500);
+        assert_eq!(at(&run("1,1,1,4,99,5,6,0,99", &[]), 0), 30);
+        assert_eq!(at(&run("2,4,4,5,99,0", &[]), 5), 9801);
+    }
+
+    #[test]
+    fn modes_and_io() {
+        assert_eq!(at(&run("1002,4,3,4,33", &[]), 4), 99);
+        assert_eq!(at(&run("1101,100,-1,4,0", &[]), 4), 99);
+        assert_eq!(run("3,0,4,0,99", &[42]).output, vec![42]);
+    }
+
+    #[test]
+    fn compare_and_jump() {
+        let equal_to_8 = "3,9,8,9,10,9,4,9,99,-1,8";
+        assert_eq!(run(equal_to_8, &[8]).output, vec![1]);
+        assert_eq!(run(equal_to_8, &[7]).output, vec![0]);
+        let less_than_8 = "3,3,1107,-1,8,3,4,3,99";
+        assert_eq!(run(less_than_8, &[7]).output, vec![1]);
+        assert_eq!(run(less_than_8, &[9]).output, vec![0]);
+
+        let nonzero = "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9";
+        assert_eq!(run(nonzero, &[0]).output, vec![0]);
+        assert_eq!(run(nonzero, &[5]).output, vec![1]);
+        let nonzero = "3,3,1105,-1,9,1101,0,0,12,4,12,99,1";
+        assert_eq!(run(nonzero, &[0]).output, vec![0]);
+        assert_eq!(run(nonzero, &[5]).output, vec![1]);
+
+        let compare_8 = "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,\
+                         1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,\
+                         999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+        assert_eq!(run(compare_8, &[7]).output, vec![999]);
+        assert_eq!(run(compare_8, &[8]).output, vec![1000]);
+        assert_eq!(run(compare_8, &[9]).output, vec![1001]);
+    }
+
+    #[test]
+    fn relative_base_and_growing_memory() {
+        let quine = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+        let expected: Vec<i64> = quine.split(',').map(|c| c.parse().unwrap()).collect();
+        assert_eq!(run(quine, &[]).output, expected);
+
+        let p = run("1102,34915192,34915192,7,4,7,99,0", &[]);
+        assert_eq!(p.output[0].to_string().len(), 16);
+        assert_eq!(
+            run("104,1125899906842624,99", &[]).output,
+            vec![1125899906842624]
+        );
+
+        let p = run("21101,3,4,1000,99", &[]);
+        assert_eq!(p.memory.len(), 1001);
+        assert_eq!(at(&p, 1000), 7);
+        assert_eq!(at(&p, 5000), 0);
+    }
+
+    #[test]
+    fn wait_for_input() {
+        let mut p = IntcodeProgram::parse("3,20,3,21,1,20,21,22,4,22,99").unwrap();
+        assert_eq!(p.evaluate(), Ok(Status::NeedsInput));
+        p.push_input(3);
+        assert_eq!(p.evaluate(), Ok(Status::NeedsInput));
+        p.push_input(4);
+        assert_eq!(p.evaluate(), Ok(Status::Halted));
+        assert_eq!(p.output, vec![7]);
+    }
+
+    #[test]
+    fn next_output() {
+        let mut p = IntcodeProgram::parse("104,5,3,20,4,20,99").unwrap();
+        assert_eq!(p.next_output(), Ok(Some(5)));
+        assert_eq!(p.next_output(), Ok(None));
+        p.push_input(6);
+        assert_eq!(p.next_output(), Ok(Some(6)));
+        assert_eq!(p.next_output(), Err(VmError::UnexpectedHalt { pc: 6 }));
+    }
+
+    #[test]
+    fn run_to_halt() {
+        let mut p = IntcodeProgram::parse("1101,2,3,5,99,0").unwrap();
+        assert_eq!(p.run_to_halt(2), Ok(()));
+        assert_eq!(p.memory.read(5), Ok(5));
+
+        // Needing input is not halting.
+        let mut p = IntcodeProgram::parse("3,3,99,0").unwrap();
+        assert_eq!(p.run_to_halt(10), Err(VmError::UnexpectedInput { pc: 0 }));
+
+        // A jump to itself never halts.
+        let mut p = IntcodeProgram::parse("1105,1,0").unwrap();
+        assert_eq!(p.run_to_halt(1000), Err(VmError::StepLimit(1000)));
+        assert_eq!(
+            VmError::StepLimit(1000).to_string(),
+            "still running after 1000 steps"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = |program: &str| IntcodeProgram::parse(program).unwrap().evaluate();
+        assert_eq!(
+            err("1,0,0,0,42"),
+            Err(VmError::BadOpcode { pc: 4, instr: 42 })
+        );
+        assert_eq!(
+            err("301,0,0,0"),
+            Err(VmError::BadOpcode { pc: 0, instr: 301 })
+        );
+        assert_eq!(
+            err("10099"),
+            Err(VmError::BadOpcode {
+                pc: 0,
+                instr: 10099
+            })
+        );
+        assert_eq!(err("-1"), Err(VmError::BadOpcode { pc: 0, instr: -1 }));
+        assert_eq!(err("1,-3,0,0,99"), Err(VmError::AddressOutOfRange(-3)));
+        assert_eq!(
+            err("109,-5,22201,0,0,0,99"),
+            Err(VmError::AddressOutOfRange(-5))
+        );
+        assert_eq!(err("1105,1,-1"), Err(VmError::AddressOutOfRange(-1)));
+        assert_eq!(err("0"), Err(VmError::BadOpcode { pc: 0, instr: 0 }));
+
+        let max = i64::MAX;
+        assert_eq!(
+            err(&format!("1101,{},1,0,99", max)),
+            Err(VmError::Overflow { pc: 0 })
+        );
+        assert_eq!(
+            err(&format!("1,0,0,0,1102,{},3,0,99", max / 2)),
+            Err(VmError::Overflow { pc: 4 })
+        );
+        assert_eq!(
+            err(&format!("109,{},109,1,99", max)),
+            Err(VmError::Overflow { pc: 2 })
+        );
+        assert_eq!(
+            err(&format!("109,{},204,1,99", max)),
+            Err(VmError::Overflow { pc: 2 })
+        );
+        assert_eq!(
+            VmError::Overflow { pc: 2 }.to_string(),
+            "arithmetic overflow at address 2"
+        );
+    }
+
+    // A small linear congruential generator, so the property test needs
+    // no crates and always draws the same programs.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((self.0 >> 33) % n as u64) as usize
+        }
+    }
+
+    // The day 2 machine as the puzzle first describes it: positional
+    // ADD and MULT, four cells at a time, until HALT.  Gives up on
+    // anything else, including arithmetic that overflows.
+    fn reference(mut memory: Vec<i64>) -> Option<Vec<i64>> {
+        let mut pc = 0;
+        loop {
+            let cell = |i: usize| memory.get(i).copied();
+            let op = cell(pc)?;
+            if op == 99 {
+                return Some(memory);
+            }
+            let a = cell(cell(pc + 1)? as usize)?;
+            let b = cell(cell(pc + 2)? as usize)?;
+            let dest = cell(pc + 3)? as usize;
+            let value = match op {
+                1 => a.checked_add(b)?,
+                2 => a.checked_mul(b)?,
+                _ => return None,
+            };
+            *memory.get_mut(dest)? = value;
+            pc += 4;
+        }
+    }
+
+    #[test]
+    fn add_and_mult_match_reference() {
+        let mut rng = Lcg(2019);
+        let mut checked = 0;
+        for _ in 0..2000 {
+            // Some instructions, a halt, then data for them to work on.
+            // Results are only ever stored in the data, so the program
+            // cannot rewrite itself into something that is not ADD or
+            // MULT.
+            let instrs = 1 + rng.below(8);
+            let code = instrs * 4 + 1;
+            let len = code + 1 + rng.below(8);
+            let mut memory = Vec::new();
+            for _ in 0..instrs {
+                memory.push(1 + rng.below(2) as i64);
+                memory.push(rng.below(len) as i64);
+                memory.push(rng.below(len) as i64);
+                memory.push((code + rng.below(len - code)) as i64);
+            }
+            memory.push(99);
+            while memory.len() < len {
+                memory.push(rng.below(50) as i64);
+            }
+
+            let expected = match reference(memory.clone()) {
+                Some(m) => m,
+                None => continue,
+            };
+            let mut p = IntcodeProgram::new(memory);
+            assert_eq!(p.evaluate(), Ok(Status::Halted));
+            assert_eq!(p.memory.as_slice(), expected.as_slice());
+            checked += 1;
+        }
+        assert!(checked > 1000, "only {} programs checked", checked);
+    }
+}
@@ -1,89 +1,146 @@
+mod asm;
+mod disasm;
+
+// The complete machine is more than day 2 needs, so some of it goes
+// unused here.
+#[allow(dead_code)]
+mod intcode;
+#[allow(dead_code)]
+mod memory;
+
+use anyhow::{anyhow, Context, Result};
+use intcode::{IntcodeProgram, VmError};
+use std::env;
 use std::fs;
-use std::vec::Vec;
-
-#[derive(Debug, PartialEq)]
-enum OpCode {
-    ADD,
-    MULT,
-    HALT,
-}
-
-#[derive(Debug)]
-struct IntcodeProgram {
-    pc: usize,
-    instructions: Vec<usize>,
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// The output that part two searches for.
+const TARGET: i64 = 19690720;
+
+/// The most steps a program may take before it is taken to be looping.
+/// Day 2 programs run straight through to their halt in a few dozen
+/// steps, but a patched noun or verb can send one into a loop, which
+/// would otherwise hang the search.
+const STEP_LIMIT: usize = 100_000;
+
+/// Run a copy of the program with the noun and verb patched into
+/// addresses 1 and 2 until it halts, returning what it leaves at
+/// address 0.
+fn run_with(program: &IntcodeProgram, noun: i64, verb: i64) -> Result<i64, VmError> {
+    let mut vm = program.clone();
+    vm.memory.write(1, noun)?;
+    vm.memory.write(2, verb)?;
+    vm.run_to_halt(STEP_LIMIT)?;
+    vm.memory.read(0)
 }
 
-impl IntcodeProgram {
-    fn new(instr: Vec<usize>) -> Self {
-        Self {
-            instructions: instr,
-            pc: 0,
+/// Search every noun and verb from 0 to 99 for the pair that makes the
+/// program output the target, returning `100 * noun + verb`.  Each
+/// thread tries every noun congruent to its number, modulo the number
+/// of threads, and all stop once one finds the pair.  Pairs that make
+/// the program fail, wait for input or loop are passed over.
+fn find_noun_verb(program: &IntcodeProgram, target: i64, threads: usize) -> Option<i64> {
+    let found = AtomicBool::new(false);
+    let answer = Mutex::new(None);
+
+    thread::scope(|s| {
+        for t in 0..threads {
+            let (found, answer) = (&found, &answer);
+            s.spawn(move || {
+                for noun in (t as i64..100).step_by(threads) {
+                    for verb in 0..100 {
+                        if found.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        if run_with(program, noun, verb) == Ok(target) {
+                            found.store(true, Ordering::Relaxed);
+                            *answer.lock().unwrap() = Some(100 * noun + verb);
+                            return;
+                        }
+                    }
+                }
+            });
         }
-    }
+    });
 
-    fn evaluate_one_step(&mut self) -> OpCode {
-        let op = int_to_opcode(*self.instructions.get(self.pc).unwrap());
+    answer.into_inner().unwrap()
+}
 
-        if op == OpCode::HALT {
-            return OpCode::HALT;
+// Usage:
+//   base                  solve both parts for input-big.txt
+//   base disasm [file]    list the program in file, input-big.txt by default
+//   base asm file         assemble file to comma separated integers
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let path = args.get(1).map_or("input-big.txt", |p| p.as_str());
+
+    match args.first().map(|a| a.as_str()) {
+        None => {}
+        Some("disasm") => {
+            let s = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+            let program = IntcodeProgram::parse(&s).with_context(|| format!("parsing {}", path))?;
+            print!("{}", disasm::listing(program.memory.as_slice()));
+            return Ok(());
         }
-
-        let src1 = *self.instructions.get(self.pc + 1).unwrap();
-        let src2 = *self.instructions.get(self.pc + 2).unwrap();
-        let dest = *self.instructions.get(self.pc + 3).unwrap();
-
-        let val1 = *self.instructions.get(src1).unwrap();
-        let val2 = *self.instructions.get(src2).unwrap();
-
-        self.pc += 4;
-        match op {
-            OpCode::ADD => {
-                let sum = val1 + val2;
-                self.instructions.push(sum);
-                self.instructions.swap_remove(dest);
-
-                OpCode::ADD
-            }
-            OpCode::MULT => {
-                let product = val1 * val2;
-                self.instructions.push(product);
-                self.instructions.swap_remove(dest);
-
-                OpCode::MULT
-            }
-            _ => panic!("invalid op code: {:?}", op),
+        Some("asm") => {
+            let path = args.get(1).ok_or_else(|| anyhow!("asm needs a file"))?;
+            let s = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+            let memory = asm::assemble(&s).with_context(|| format!("assembling {}", path))?;
+            println!("{}", asm::to_comma(&memory));
+            return Ok(());
         }
+        Some(other) => return Err(anyhow!("unknown command `{}`", other)),
     }
 
-    fn evaluate(&mut self) {
-        let mut op = self.evaluate_one_step();
+    let s = fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+    let program = IntcodeProgram::parse(&s).with_context(|| format!("parsing {}", path))?;
 
-        while op != OpCode::HALT {
-            op = self.evaluate_one_step();
-        }
-    }
+    // Restore the program to the "1202 program alarm" state.
+    println!("part one: {}", run_with(&program, 12, 2)?);
+
+    let threads = thread::available_parallelism().map_or(4, |n| n.get());
+    let answer = find_noun_verb(&program, TARGET, threads)
+        .ok_or_else(|| anyhow!("no noun and verb give {}", TARGET))?;
+    println!("part two: {}", answer);
+
+    Ok(())
 }
 
-fn int_to_opcode(n: usize) -> OpCode {
-    match n {
-        1 => OpCode::ADD,
-        2 => OpCode::MULT,
-        99 => OpCode::HALT,
-        _ => panic!("invalid opcode: {:?}", n),
+#[cfg(test)]
+mod tests {
+    use super::{find_noun_verb, run_with, STEP_LIMIT, TARGET};
+    use crate::intcode::{IntcodeProgram, VmError};
+
+    fn program() -> IntcodeProgram {
+        IntcodeProgram::parse(include_str!("../input-big.txt")).unwrap()
     }
-}
 
-fn main() {
-    let s = fs::read_to_string("input-big.txt").unwrap();
-    let s = s.trim();
+    #[test]
+    fn alarm_state() {
+        assert_eq!(run_with(&program(), 12, 2), Ok(6568671));
+    }
 
-    let instructions = s
-        .split(',')
-        .map(|c| c.parse::<usize>().unwrap())
-        .collect::<Vec<usize>>();
+    #[test]
+    fn search() {
+        let p = program();
+        for threads in [1, 3, 8].iter() {
+            let answer = find_noun_verb(&p, TARGET, *threads).unwrap();
+            assert_eq!(run_with(&p, answer / 100, answer % 100), Ok(TARGET));
+        }
+        assert_eq!(find_noun_verb(&p, -1, 4), None);
+    }
 
-    let mut my_program = IntcodeProgram::new(instructions);
-    my_program.evaluate();
-    println!("{:?}", my_program);
+    #[test]
+    fn must_halt() {
+        // A zero noun falls through to the halt; otherwise the verb is
+        // where to jump, to an input or to a loop.  Address 0 is the
+        // same however the program stops, but only halting counts.
+        let p = IntcodeProgram::parse("1105,0,0,99,3,0,1105,1,6").unwrap();
+        assert_eq!(run_with(&p, 0, 0), Ok(1105));
+        assert_eq!(run_with(&p, 1, 4), Err(VmError::UnexpectedInput { pc: 4 }));
+        assert_eq!(run_with(&p, 1, 6), Err(VmError::StepLimit(STEP_LIMIT)));
+        assert_eq!(find_noun_verb(&p, 1105, 2), Some(0));
+    }
 }
@@ -0,0 +1,78 @@
+use crate::intcode::VmError;
+use std::convert::TryFrom;
+
+/// The memory of an Intcode machine.  Reading past the end gives zero,
+/// and writing past the end grows memory to hold the value, up to
+/// `Memory::LIMIT` cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Memory {
+    cells: Vec<i64>,
+}
+
+impl Memory {
+    /// The most cells memory may grow to.
+    pub const LIMIT: usize = 1 << 24;
+
+    pub fn new(cells: Vec<i64>) -> Self {
+        Memory { cells }
+    }
+
+    /// The number of cells written so far, or loaded with the program.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn as_slice(&self) -> &[i64] {
+        &self.cells
+    }
+
+    /// Check that the address lies within the limit, and convert it to
+    /// an index.
+    pub fn index(addr: i64) -> Result<usize, VmError> {
+        match usize::try_from(addr) {
+            Ok(i) if i < Memory::LIMIT => Ok(i),
+            _ => Err(VmError::AddressOutOfRange(addr)),
+        }
+    }
+
+    pub fn read(&self, addr: i64) -> Result<i64, VmError> {
+        let i = Memory::index(addr)?;
+        Ok(self.cells.get(i).copied().unwrap_or(0))
+    }
+
+    pub fn write(&mut self, addr: i64, value: i64) -> Result<(), VmError> {
+        let i = Memory::index(addr)?;
+        if i >= self.cells.len() {
+            self.cells.resize(i + 1, 0);
+        }
+        self.cells[i] = value;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Memory;
+    use crate::intcode::VmError;
+
+    #[test]
+    fn checked_reads_and_writes() {
+        let mut m = Memory::new(vec![1, 2, 3]);
+        assert_eq!(m.read(2), Ok(3));
+        assert_eq!(m.read(3), Ok(0));
+        assert_eq!(m.len(), 3);
+
+        m.write(1, 7).unwrap();
+        m.write(5, 9).unwrap();
+        assert_eq!(m.as_slice(), &[1, 7, 3, 0, 0, 9]);
+
+        assert_eq!(m.read(-1), Err(VmError::AddressOutOfRange(-1)));
+        let past = Memory::LIMIT as i64;
+        assert_eq!(m.write(past, 1), Err(VmError::AddressOutOfRange(past)));
+        assert_eq!(m.len(), 6);
+    }
+}